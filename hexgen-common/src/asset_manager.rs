@@ -0,0 +1,169 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+use glium::{Display, Program, Texture2d};
+use glium::glutin::surface::WindowSurface;
+use tracing::{error, info};
+use crate::error::{Error, Result};
+use crate::material::overrides::MaterialOverrides;
+use crate::material::shader::{Shader, ShaderCache};
+use crate::material::shader::watcher::ShaderWatcher;
+use crate::model::Model;
+use crate::model::mesh::texture::TextureCache;
+
+// Owns every loaded model, texture and program, repeated loads of the same path share one copy.
+// The returned Rc is the handle, assets nothing else holds on to can be dropped with unload_unused
+pub struct AssetManager {
+    pub textures: TextureCache,
+    pub shaders: ShaderCache,
+    models: HashMap<String, Rc<RefCell<Model>>>,
+    material_overrides: MaterialOverrides,
+    shader_watcher: Option<ShaderWatcher>,
+    missing_model: Option<Rc<RefCell<Model>>>,
+    errors: Vec<String>,
+}
+
+impl Default for AssetManager {
+    fn default() -> Self {
+        AssetManager::new()
+    }
+}
+
+impl AssetManager {
+    pub fn new() -> AssetManager {
+        AssetManager {
+            textures: TextureCache::new(),
+            shaders: ShaderCache::new(),
+            models: HashMap::new(),
+            material_overrides: MaterialOverrides::new(),
+            shader_watcher: None,
+            missing_model: None,
+            errors: vec![],
+        }
+    }
+
+    // Applied to every model loaded afterwards
+    pub fn set_material_overrides(&mut self, material_overrides: MaterialOverrides) {
+        self.material_overrides = material_overrides;
+    }
+
+    pub fn load_model(&mut self, path: &str, display: &Display<WindowSurface>) -> Result<Rc<RefCell<Model>>> {
+        if let Some(model) = self.models.get(path) {
+            return Ok(model.clone());
+        }
+
+        let name = Path::new(path).file_stem().map_or(path.to_string(), |stem| stem.to_string_lossy().to_string());
+        let mut model = Model::new(name);
+        if let Err(e) = model.load_model(path, display, &mut self.textures, &mut self.shaders) {
+            self.report(&e);
+            return Err(e);
+        }
+        // A broken override shader leaves the material on its default program rather than failing the model
+        if let Err(e) = model.apply_material_overrides(&self.material_overrides, display, &mut self.shaders) {
+            self.report(&e);
+        }
+
+        let model = Rc::new(RefCell::new(model));
+        self.models.insert(path.to_string(), model.clone());
+        Ok(model)
+    }
+
+    // Never fails, models that can't be loaded are replaced by the shared missing model
+    pub fn load_model_or_missing(&mut self, path: &str, display: &Display<WindowSurface>) -> Rc<RefCell<Model>> {
+        match self.load_model(path, display) {
+            Ok(model) => model,
+            Err(_) => self.missing_model(display),
+        }
+    }
+
+    pub fn missing_model(&mut self, display: &Display<WindowSurface>) -> Rc<RefCell<Model>> {
+        if let Some(model) = &self.missing_model {
+            return model.clone();
+        }
+        let model = match Model::missing(display, &mut self.shaders) {
+            Ok(model) => model,
+            Err(e) => {
+                self.report(&e);
+                Model::new(String::from("Missing model"))
+            }
+        };
+        let model = Rc::new(RefCell::new(model));
+        self.missing_model = Some(model.clone());
+        model
+    }
+
+    pub fn load_texture(&mut self, path: &str, display: &Display<WindowSurface>) -> Result<Rc<Texture2d>> {
+        self.textures.load(path, display).inspect_err(|e| self.report(e))
+    }
+
+    pub fn load_shader(&mut self, shader: &Shader, display: &Display<WindowSurface>) -> Result<Rc<Program>> {
+        self.shaders.load(shader, display).inspect_err(|e| self.report(e))
+    }
+
+    // Registers a model built in code under a key so it can be shared like a loaded one
+    pub fn add_model(&mut self, key: &str, mut model: Model, display: &Display<WindowSurface>) -> Rc<RefCell<Model>> {
+        if let Err(e) = model.apply_material_overrides(&self.material_overrides, display, &mut self.shaders) {
+            self.report(&e);
+        }
+        let model = Rc::new(RefCell::new(model));
+        self.models.insert(key.to_string(), model.clone());
+        model
+    }
+
+    pub fn model(&self, path: &str) -> Option<Rc<RefCell<Model>>> {
+        self.models.get(path).cloned()
+    }
+
+    pub fn models(&self) -> impl Iterator<Item = &Rc<RefCell<Model>>> {
+        self.models.values()
+    }
+
+    pub fn watch_shaders(&mut self, directory: &str) {
+        self.shader_watcher = ShaderWatcher::new(directory);
+    }
+
+    // Recompiles shaders edited on disk and swaps them into every loaded model.
+    // The reloaded programs are returned so passes outside of models can pick them up too
+    pub fn reload_changed_shaders(&mut self, display: &Display<WindowSurface>) -> Vec<(Shader, Rc<Program>)> {
+        let changed_paths = match &self.shader_watcher {
+            Some(shader_watcher) => shader_watcher.changed_paths(),
+            None => return vec![],
+        };
+        if changed_paths.is_empty() {
+            return vec![];
+        }
+
+        let reloaded = self.shaders.reload(&changed_paths, display);
+        for model in self.models.values().chain(self.missing_model.iter()) {
+            model.borrow_mut().swap_programs(&reloaded);
+        }
+        reloaded
+    }
+
+    // Drops every asset only the manager still references, returns how many were released
+    pub fn unload_unused(&mut self) -> usize {
+        let models_before = self.models.len();
+        self.models.retain(|_, model| Rc::strong_count(model) > 1);
+        let unloaded = models_before - self.models.len()
+            + self.textures.unload_unused()
+            + self.shaders.unload_unused();
+
+        if unloaded > 0 {
+            info!("Unloaded {} unused assets", unloaded);
+        }
+        unloaded
+    }
+
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    fn report(&mut self, error: &Error) {
+        let message = error.to_string();
+        error!("{}", message);
+        if !self.errors.contains(&message) {
+            self.errors.push(message);
+        }
+    }
+}
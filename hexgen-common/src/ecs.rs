@@ -0,0 +1,245 @@
+pub mod components;
+pub mod sparse_set;
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use crate::ecs::sparse_set::SparseSet;
+
+// Handle to an entity, stays invalid once the entity is despawned even if its slot gets reused
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+// Type-erased component storage so the world can drop an entity's components without knowing their types
+trait Storage {
+    fn remove_entity(&mut self, entity: Entity);
+    fn clear(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> Storage for SparseSet<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn clear(&mut self) {
+        SparseSet::clear(self);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Entities are plain ids, any 'static type can be attached to them as a component, one per type
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn Storage>>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+impl World {
+    pub fn new() -> World {
+        World {
+            generations: vec![],
+            alive: vec![],
+            free: vec![],
+            storages: HashMap::new(),
+        }
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity { index, generation: self.generations[index as usize] }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity { index: self.generations.len() as u32 - 1, generation: 0 }
+            }
+        }
+    }
+
+    // Drops every component of the entity, returns false if it was already gone
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+        let index = entity.index();
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        true
+    }
+
+    // Every slot becomes free with its generation bumped, so entities from before the clear stay dead
+    pub fn clear(&mut self) {
+        for storage in self.storages.values_mut() {
+            storage.clear();
+        }
+        for (generation, alive) in self.generations.iter_mut().zip(self.alive.iter_mut()) {
+            if *alive {
+                *generation = generation.wrapping_add(1);
+                *alive = false;
+            }
+        }
+        self.free = (0..self.generations.len() as u32).rev().collect();
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.alive.get(entity.index()).is_some_and(|alive| *alive)
+            && self.generations[entity.index()] == entity.generation
+    }
+
+    // Ignored for despawned entities, returns the component it replaced
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>().insert(entity, component)
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.existing_storage_mut::<T>()?.remove(entity)
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        self.storage::<T>()?.get(entity)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.existing_storage_mut::<T>()?.get_mut(entity)
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.storage::<T>().is_some_and(|storage| storage.contains(entity))
+    }
+
+    pub fn query<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>().into_iter().flat_map(|storage| storage.iter())
+    }
+
+    pub fn query_mut<T: 'static>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.existing_storage_mut::<T>().into_iter().flat_map(|storage| storage.iter_mut())
+    }
+
+    // Entities having both components
+    pub fn query2<A: 'static, B: 'static>(&self) -> impl Iterator<Item = (Entity, &A, &B)> {
+        let second = self.storage::<B>();
+        self.query::<A>().filter_map(move |(entity, a)| second?.get(entity).map(|b| (entity, a, b)))
+    }
+
+    pub fn count<T: 'static>(&self) -> usize {
+        self.storage::<T>().map_or(0, |storage| storage.len())
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&SparseSet<T>> {
+        self.storages.get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref::<SparseSet<T>>())
+    }
+
+    fn existing_storage_mut<T: 'static>(&mut self) -> Option<&mut SparseSet<T>> {
+        self.storages.get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<SparseSet<T>>())
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> &mut SparseSet<T> {
+        self.storages.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseSet::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()
+            .expect("component storage is keyed by its type")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn despawn_drops_components() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        let other = world.spawn();
+        world.insert(entity, 1u32);
+        world.insert(entity, "name");
+        world.insert(other, 2u32);
+
+        assert!(world.despawn(entity));
+        assert!(!world.despawn(entity));
+        assert!(!world.is_alive(entity));
+        assert_eq!(world.get::<u32>(entity), None);
+        assert_eq!(world.count::<u32>(), 1);
+        assert_eq!(world.count::<&str>(), 0);
+        assert_eq!(world.get::<u32>(other), Some(&2));
+
+        // The slot is reused, but the old handle doesn't see the new entity's components
+        let reused = world.spawn();
+        assert_eq!(reused.index(), entity.index());
+        world.insert(reused, 3u32);
+        assert_eq!(world.get::<u32>(entity), None);
+        assert_eq!(world.insert(entity, 4u32), None);
+        assert_eq!(world.get::<u32>(reused), Some(&3));
+    }
+
+    #[test]
+    fn query2_yields_entities_with_both_components() {
+        let mut world = World::new();
+        let both = world.spawn();
+        let only_first = world.spawn();
+        let only_second = world.spawn();
+        let also_both = world.spawn();
+        world.insert(both, 1u32);
+        world.insert(both, 'a');
+        world.insert(only_first, 2u32);
+        world.insert(only_second, 'b');
+        world.insert(also_both, 'c');
+        world.insert(also_both, 4u32);
+
+        let mut found: Vec<(Entity, u32, char)> = world.query2::<u32, char>().map(|(entity, a, b)| (entity, *a, *b)).collect();
+        found.sort_by_key(|(entity, _, _)| entity.index());
+        assert_eq!(found, vec![(both, 1, 'a'), (also_both, 4, 'c')]);
+        assert_eq!(world.query2::<u32, f32>().count(), 0);
+    }
+
+    #[test]
+    fn clear_invalidates_entities() {
+        let mut world = World::new();
+        let old = world.spawn();
+        world.insert(old, 1u32);
+        world.clear();
+
+        let new = world.spawn();
+        assert_ne!(old, new);
+        assert!(!world.is_alive(old));
+        assert!(world.is_alive(new));
+        world.insert(new, 2u32);
+        assert_eq!(world.get::<u32>(old), None);
+        assert_eq!(world.count::<u32>(), 1);
+    }
+}
@@ -0,0 +1,24 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::model::Model;
+use crate::scene::NodeId;
+
+// Drawn by the renderer at the world matrix of the entity's scene node
+pub struct Renderable {
+    pub model: Rc<RefCell<Model>>,
+    // Bit i is set when the neighbouring tile at i * 60 degrees is land, used for shoreline effects
+    pub shore_mask: u8,
+}
+
+impl Renderable {
+    pub fn new(model: Rc<RefCell<Model>>) -> Renderable {
+        Renderable {
+            model,
+            shore_mask: 0,
+        }
+    }
+}
+
+// Added by the scene to every entity it owns, links the entity back to its node
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SceneNode(pub NodeId);
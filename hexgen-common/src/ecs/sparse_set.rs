@@ -0,0 +1,145 @@
+use crate::ecs::Entity;
+
+// Components packed densely for iteration, with a sparse index from entity to position for lookups
+pub struct SparseSet<T> {
+    sparse: Vec<Option<usize>>,
+    entities: Vec<Entity>,
+    components: Vec<T>,
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        SparseSet::new()
+    }
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> SparseSet<T> {
+        SparseSet {
+            sparse: vec![],
+            entities: vec![],
+            components: vec![],
+        }
+    }
+
+    // Replaces and returns the entity's previous component
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(dense) = self.dense_index(entity) {
+            return Some(std::mem::replace(&mut self.components[dense], component));
+        }
+        let index = entity.index();
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, None);
+        }
+        // A stale component from a despawned entity in the same slot is dropped first
+        if self.sparse[index].is_some() {
+            self.remove_at(index);
+        }
+        self.sparse[index] = Some(self.entities.len());
+        self.entities.push(entity);
+        self.components.push(component);
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.dense_index(entity)?;
+        self.remove_at(entity.index())
+    }
+
+    // Swap-removes so the dense arrays stay packed
+    fn remove_at(&mut self, index: usize) -> Option<T> {
+        let dense = self.sparse.get_mut(index)?.take()?;
+        let last = self.entities.len() - 1;
+        self.entities.swap(dense, last);
+        self.components.swap(dense, last);
+        if dense != last {
+            self.sparse[self.entities[dense].index()] = Some(dense);
+        }
+        self.entities.pop();
+        self.components.pop()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.dense_index(entity).map(|dense| &self.components[dense])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.dense_index(entity).map(|dense| &mut self.components[dense])
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dense_index(entity).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.components.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(self.components.iter_mut())
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.sparse.clear();
+        self.entities.clear();
+        self.components.clear();
+    }
+
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        let dense = (*self.sparse.get(entity.index())?)?;
+        (self.entities[dense] == entity).then_some(dense)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(index: u32) -> Entity {
+        Entity { index, generation: 0 }
+    }
+
+    #[test]
+    fn remove_reindexes_swapped_component() {
+        let mut set = SparseSet::new();
+        for index in 0..4 {
+            set.insert(entity(index), index * 10);
+        }
+        assert_eq!(set.remove(entity(1)), Some(10));
+        // The last component moved into the hole and is still found through its entity
+        assert_eq!(set.entities(), &[entity(0), entity(3), entity(2)]);
+        assert_eq!(set.get(entity(3)), Some(&30));
+        assert_eq!(set.get(entity(2)), Some(&20));
+        assert_eq!(set.get(entity(1)), None);
+
+        assert_eq!(set.remove(entity(2)), Some(20));
+        assert_eq!(set.remove(entity(0)), Some(0));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![(entity(3), &30)]);
+        assert_eq!(set.remove(entity(0)), None);
+    }
+
+    #[test]
+    fn stale_entity_is_replaced() {
+        let mut set = SparseSet::new();
+        set.insert(entity(0), 1);
+        set.insert(entity(1), 2);
+        let newer = Entity { index: 0, generation: 1 };
+        assert_eq!(set.insert(newer, 3), None);
+        assert_eq!(set.get(entity(0)), None);
+        assert_eq!(set.get(newer), Some(&3));
+        assert_eq!(set.get(entity(1)), Some(&2));
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use crate::material::shader::preprocessor::PreprocessError;
+
+// Everything that can go wrong while loading assets, each variant carries the path or stage involved
+#[derive(Debug)]
+pub enum Error {
+    Io { path: PathBuf, source: io::Error },
+    EmptyFile { path: PathBuf },
+    ModelImport { path: PathBuf, message: String },
+    Texture { path: PathBuf, message: String },
+    Preprocess(PreprocessError),
+    ShaderCompilation { vertex_path: String, fragment_path: String, message: String },
+    Buffer { kind: &'static str, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::EmptyFile { path } => write!(f, "{}: file is empty", path.display()),
+            Error::ModelImport { path, message } => write!(f, "{}: couldn't import model: {}", path.display(), message),
+            Error::Texture { path, message } => write!(f, "{}: couldn't load texture: {}", path.display(), message),
+            Error::Preprocess(error) => write!(f, "{}", error),
+            Error::ShaderCompilation { vertex_path, fragment_path, message } => {
+                write!(f, "{} + {}: couldn't compile program: {}", vertex_path, fragment_path, message)
+            }
+            Error::Buffer { kind, message } => write!(f, "couldn't create {} buffer: {}", kind, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<PreprocessError> for Error {
+    fn from(error: PreprocessError) -> Error {
+        Error::Preprocess(error)
+    }
+}
@@ -0,0 +1,131 @@
+use std::ops::{Index, IndexMut};
+
+// One value per cell of a width by height map, stored row by row. Shared by the map and everything
+// laid over it, so cell coordinates and indices mean the same everywhere
+#[derive(Clone, Debug)]
+pub struct Grid<T> {
+    width: u8,
+    height: u8,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: u8, height: u8, value: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width as usize * height as usize],
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    // Takes signed coordinates so neighbours of border cells can be checked directly
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        x >= 0 && z >= 0 && x < self.width as i32 && z < self.height as i32
+    }
+
+    // None outside the grid
+    pub fn index(&self, x: u8, z: u8) -> Option<usize> {
+        self.contains(x as i32, z as i32).then_some(z as usize * self.width as usize + x as usize)
+    }
+
+    pub fn coordinates(&self, index: usize) -> (u8, u8) {
+        ((index % self.width as usize) as u8, (index / self.width as usize) as u8)
+    }
+
+    pub fn get(&self, x: u8, z: u8) -> Option<&T> {
+        self.index(x, z).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, x: u8, z: u8) -> Option<&mut T> {
+        self.index(x, z).map(|index| &mut self.cells[index])
+    }
+
+    // Ignored outside the grid
+    pub fn set(&mut self, x: u8, z: u8, value: T) {
+        if let Some(cell) = self.get_mut(x, z) {
+            *cell = value;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((u8, u8), &T)> {
+        self.cells.iter().enumerate().map(|(index, cell)| (self.coordinates(index), cell))
+    }
+
+    // Row by row, in index order
+    pub fn values(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+}
+
+impl<T> Index<usize> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.cells[index]
+    }
+}
+
+impl<T> IndexMut<usize> for Grid<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.cells[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_stored_row_by_row() {
+        let mut grid = Grid::new(3, 2, 0);
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid.index(2, 1), Some(5));
+        assert_eq!(grid.index(3, 0), None);
+        assert_eq!(grid.index(0, 2), None);
+        for index in 0..grid.len() {
+            let (x, z) = grid.coordinates(index);
+            assert_eq!(grid.index(x, z), Some(index));
+        }
+
+        grid.set(1, 1, 7);
+        grid.set(5, 5, 9);
+        assert_eq!(grid[4], 7);
+        assert_eq!(grid.get(1, 1), Some(&7));
+        assert_eq!(grid.get(5, 5), None);
+        assert_eq!(grid.iter().filter(|(_, value)| **value == 7).map(|(cell, _)| cell).collect::<Vec<_>>(), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn contains_takes_cells_outside() {
+        let grid = Grid::new(4, 3, false);
+        assert!(grid.contains(0, 0) && grid.contains(3, 2));
+        assert!(!grid.contains(-1, 0) && !grid.contains(0, -1) && !grid.contains(4, 0) && !grid.contains(0, 3));
+        assert!(Grid::new(0, 0, 0).is_empty());
+    }
+}
@@ -1,6 +1,14 @@
+pub mod asset_manager;
+pub mod ecs;
+pub mod error;
+pub mod grid;
 pub mod model;
 pub mod game_object;
+pub mod vector2;
 pub mod vector3;
+pub mod vector4;
 pub mod material;
 pub mod transform;
-pub mod matrix;
\ No newline at end of file
+pub mod matrix;
+pub mod quaternion;
+pub mod scene;
\ No newline at end of file
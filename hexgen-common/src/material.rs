@@ -1,18 +1,30 @@
-use glium::{Display, Program};
+use std::rc::Rc;
+use glium::{Display, Program, Texture2d};
 use glium::glutin::surface::WindowSurface;
 use tracing::info;
-use shader::Shader;
+use overrides::MaterialOverride;
+use shader::{Shader, ShaderCache};
+use crate::error::Result;
 use crate::vector3::Vector3;
 
 pub mod shader;
+pub mod overrides;
+
+// Specular exponent used when the material file doesn't define one
+pub const DEFAULT_SHININESS: f32 = 16.0;
 
 pub struct Material {
     pub name: String,
     pub shader: Shader,
-    pub program: Option<Program>,
+    pub program: Option<Rc<Program>>,
     pub ambient_color: Vector3,
     pub diffuse_color: Vector3,
     pub specular_color: Vector3,
+    pub emissive_color: Vector3,
+    pub shininess: f32,
+    pub opacity: f32,
+    pub diffuse_texture: Option<Rc<Texture2d>>,
+    pub normal_texture: Option<Rc<Texture2d>>,
 }
 
 impl Material {
@@ -24,11 +36,52 @@ impl Material {
             ambient_color,
             diffuse_color,
             specular_color,
+            emissive_color: Vector3::zero(),
+            shininess: DEFAULT_SHININESS,
+            opacity: 1.0,
+            diffuse_texture: None,
+            normal_texture: None,
         }
     }
 
-    pub fn load_material(&mut self, display: &Display<WindowSurface>) {
-        self.program = Some(glium::Program::from_source(display, &self.shader.vertex, &self.shader.fragment, None).unwrap());
+    // On failure the previous program, if any, stays in use
+    pub fn load_material(&mut self, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<()> {
+        self.program = Some(shader_cache.load(&self.shader, display)?);
         info!("Loaded material '{}'", self.name);
+        Ok(())
+    }
+
+    // Picks up a recompiled program if this material was built from the same shader
+    pub fn swap_program(&mut self, reloaded: &[(Shader, Rc<Program>)]) {
+        if let Some((_, program)) = reloaded.iter().find(|(shader, _)| shader == &self.shader) {
+            self.program = Some(program.clone());
+            info!("Reloaded program for material '{}'", self.name);
+        }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.opacity < 1.0
+    }
+
+    pub fn set_shader(&mut self, shader: Shader, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<()> {
+        self.shader = shader;
+        self.load_material(display, shader_cache)
+    }
+
+    pub fn apply_override(&mut self, material_override: &MaterialOverride, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<()> {
+        if let Some(shininess) = material_override.shininess {
+            self.shininess = shininess;
+        }
+        if let Some(opacity) = material_override.opacity {
+            self.opacity = opacity.clamp(0.0, 1.0);
+        }
+        if let Some(emissive_color) = material_override.emissive_color {
+            self.emissive_color = emissive_color;
+        }
+        if let (Some(vertex), Some(fragment)) = (&material_override.vertex_shader, &material_override.fragment_shader) {
+            self.set_shader(Shader::new(vertex, fragment), display, shader_cache)?;
+        }
+        info!("Applied override to material '{}'", self.name);
+        Ok(())
     }
 }
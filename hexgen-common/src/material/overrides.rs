@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::fs;
+use tracing::{error, info, warn};
+use crate::vector3::Vector3;
+
+// Settings that replace what the model's material file defines for every material with a given name
+#[derive(Clone, Default)]
+pub struct MaterialOverride {
+    pub vertex_shader: Option<String>,
+    pub fragment_shader: Option<String>,
+    pub shininess: Option<f32>,
+    pub opacity: Option<f32>,
+    pub emissive_color: Option<Vector3>,
+}
+
+// Override file is a list of sections named after materials:
+//
+// [water]
+// vertex = ./res/shaders/water.vert
+// fragment = ./res/shaders/water.frag
+// shininess = 128
+// opacity = 0.85
+// emissive = 0.0 0.0 0.0
+pub struct MaterialOverrides {
+    pub overrides: HashMap<String, MaterialOverride>,
+}
+
+impl Default for MaterialOverrides {
+    fn default() -> Self {
+        MaterialOverrides::new()
+    }
+}
+
+impl MaterialOverrides {
+    pub fn new() -> MaterialOverrides {
+        MaterialOverrides {
+            overrides: HashMap::new(),
+        }
+    }
+
+    pub fn load(file_path: &str) -> MaterialOverrides {
+        let mut material_overrides = MaterialOverrides::new();
+        let content = match fs::read_to_string(file_path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("No material overrides loaded from {}: {}", file_path, e);
+                return material_overrides;
+            }
+        };
+
+        let mut current: Option<String> = None;
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = line[1..line.len() - 1].trim().to_string();
+                material_overrides.overrides.entry(name.clone()).or_default();
+                current = Some(name);
+                continue;
+            }
+
+            let (material_name, (key, value)) = match (&current, line.split_once('=')) {
+                (Some(material_name), Some((key, value))) => (material_name, (key.trim(), value.trim())),
+                _ => {
+                    error!("{}:{}: expected '[material]' or 'key = value'", file_path, line_number + 1);
+                    continue;
+                }
+            };
+            let material_override = material_overrides.overrides.get_mut(material_name).unwrap();
+            let parsed = match key {
+                "vertex" => {
+                    material_override.vertex_shader = Some(value.to_string());
+                    true
+                }
+                "fragment" => {
+                    material_override.fragment_shader = Some(value.to_string());
+                    true
+                }
+                "shininess" => value.parse().map(|v| material_override.shininess = Some(v)).is_ok(),
+                "opacity" => value.parse().map(|v| material_override.opacity = Some(v)).is_ok(),
+                "emissive" => {
+                    let values: Vec<f32> = value.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+                    if values.len() == 3 {
+                        material_override.emissive_color = Some(Vector3::new(values[0], values[1], values[2]));
+                    }
+                    values.len() == 3
+                }
+                _ => {
+                    error!("{}:{}: unknown key '{}'", file_path, line_number + 1, key);
+                    continue;
+                }
+            };
+            if !parsed {
+                error!("{}:{}: invalid value '{}' for '{}'", file_path, line_number + 1, value, key);
+            }
+        }
+
+        for (name, material_override) in &material_overrides.overrides {
+            if material_override.vertex_shader.is_some() != material_override.fragment_shader.is_some() {
+                error!("{}: material '{}' has to override both the vertex and fragment shader", file_path, name);
+            }
+        }
+
+        info!("Loaded {} material overrides from {}", material_overrides.overrides.len(), file_path);
+        material_overrides
+    }
+
+    pub fn get(&self, material_name: &str) -> Option<&MaterialOverride> {
+        self.overrides.get(material_name)
+    }
+}
@@ -1,38 +1,257 @@
-use std::fs::File;
-use std::io::Read;
-use tracing::{error, info};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use glium::{Display, Program, ProgramCreationError};
+use glium::program::ShaderType;
+use glium::glutin::surface::WindowSurface;
+use tracing::{error, info, warn};
+use preprocessor::{ExpandedSource, PreprocessError, PreprocessedSource};
+use crate::error::{Error, Result};
 
-#[derive(Clone)]
+pub mod preprocessor;
+pub mod watcher;
+
+pub const DIFFUSE_VERTEX_SHADER: &str = "./res/shaders/diffuse.vert";
+pub const DIFFUSE_FRAGMENT_SHADER: &str = "./res/shaders/diffuse.frag";
+
+// Describes a program by its source files and the defines injected into them, used as the cache key
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Shader {
-    pub vertex: String,
-    pub fragment: String,
+    pub vertex_path: String,
+    pub fragment_path: String,
+    pub defines: Vec<(String, String)>,
 }
 
 impl Shader {
     pub fn new(vertex_filepath: &str, fragment_filepath: &str) -> Shader {
-        let mut vertex_file = File::open(vertex_filepath).unwrap_or_else(|_| { panic!("Cannot open vertex shader file: {}", vertex_filepath) });
-        let mut vertex_content = String::new();
-        vertex_file.read_to_string(&mut vertex_content).unwrap_or_else(|_| { 0 });
-
-        if vertex_content.len() != 0 {
-            info!("Loaded vertex shader at path {}", vertex_filepath);
-        } else {
-            error!("Error loading vertex shader at path {}", vertex_filepath);
+        Shader {
+            vertex_path: vertex_filepath.to_string(),
+            fragment_path: fragment_filepath.to_string(),
+            defines: vec![],
         }
+    }
+
+    pub fn with_define(mut self, name: &str, value: &str) -> Shader {
+        self.defines.retain(|(existing, _)| existing != name);
+        self.defines.push((name.to_string(), value.to_string()));
+        self.defines.sort();
+        self
+    }
+
+    // Expands both stages with the shader's own defines appended to the shared ones.
+    // Both stages get the highest #version either of them asks for so they always link
+    pub fn preprocess(&self, shared_defines: &[(String, String)]) -> std::result::Result<ShaderSources, PreprocessError> {
+        let vertex = ExpandedSource::load(Path::new(&self.vertex_path))?;
+        let fragment = ExpandedSource::load(Path::new(&self.fragment_path))?;
+        let version = [&vertex.version, &fragment.version].into_iter()
+            .flatten()
+            .max_by_key(|version| version.number)
+            .cloned()
+            .unwrap_or_default();
 
-        let mut fragment_file = File::open(fragment_filepath).unwrap_or_else(|_| { panic!("Cannot open fragment shader file: {}", fragment_filepath) });
-        let mut fragment_content = String::new();
-        fragment_file.read_to_string(&mut fragment_content).unwrap_or_else(|_| { 0 });
+        let mut defines: Vec<(String, String)> = shared_defines.iter()
+            .filter(|(name, _)| !self.defines.iter().any(|(own, _)| own == name))
+            .cloned()
+            .collect();
+        defines.extend(self.defines.iter().cloned());
 
-        if fragment_content.len() != 0 {
-            info!("Loaded fragment shader at path {}", vertex_filepath);
-        } else {
-            error!("Error loading fragment shader at path {}", vertex_filepath);
+        info!("Preprocessed shaders {} + {}", self.vertex_path, self.fragment_path);
+        Ok(ShaderSources {
+            vertex: vertex.finish(&version, &defines),
+            fragment: fragment.finish(&version, &defines),
+        })
+    }
+}
+
+pub struct ShaderSources {
+    pub vertex: PreprocessedSource,
+    pub fragment: PreprocessedSource,
+}
+
+impl ShaderSources {
+    // Every file pasted into either stage, a change to any of them requires a recompile
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = self.vertex.files.clone();
+        for file in &self.fragment.files {
+            if !files.contains(file) {
+                files.push(file.clone());
+            }
         }
+        files
+    }
+}
 
-        Shader {
-            vertex: vertex_content,
-            fragment: fragment_content,
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ShaderCacheStats {
+    pub compiled: u32,
+    pub failed: u32,
+    pub hits: u32,
+    pub compile_time: Duration,
+}
+
+// Compiles each distinct program once and shares it between every material using it
+pub struct ShaderCache {
+    programs: HashMap<Shader, Rc<Program>>,
+    // Source files including includes that each program was built from
+    dependencies: HashMap<Shader, Vec<PathBuf>>,
+    // Last compile error per program, cleared once it compiles again
+    errors: HashMap<Shader, String>,
+    // Injected into every program, set these before loading anything
+    defines: Vec<(String, String)>,
+    stats: ShaderCacheStats,
+}
+
+impl Default for ShaderCache {
+    fn default() -> Self {
+        ShaderCache::new()
+    }
+}
+
+impl ShaderCache {
+    pub fn new() -> ShaderCache {
+        ShaderCache {
+            programs: HashMap::new(),
+            dependencies: HashMap::new(),
+            errors: HashMap::new(),
+            defines: vec![],
+            stats: ShaderCacheStats::default(),
+        }
+    }
+
+    pub fn set_define(&mut self, name: &str, value: &str) {
+        self.defines.retain(|(existing, _)| existing != name);
+        self.defines.push((name.to_string(), value.to_string()));
+    }
+
+    pub fn load(&mut self, shader: &Shader, display: &Display<WindowSurface>) -> Result<Rc<Program>> {
+        if let Some(program) = self.programs.get(shader) {
+            self.stats.hits += 1;
+            return Ok(program.clone());
+        }
+
+        let program = self.compile(shader, display)?;
+        self.programs.insert(shader.clone(), program.clone());
+        Ok(program)
+    }
+
+    // Recompiles every cached program built from one of the changed files.
+    // Programs that fail to compile keep their previous version, only the successful ones are returned
+    pub fn reload(&mut self, changed_paths: &[PathBuf], display: &Display<WindowSurface>) -> Vec<(Shader, Rc<Program>)> {
+        let affected: Vec<Shader> = self.programs.keys()
+            .chain(self.errors.keys())
+            .filter(|shader| self.depends_on(shader, changed_paths))
+            .cloned()
+            .collect();
+
+        let mut reloaded = vec![];
+        for shader in affected {
+            if reloaded.iter().any(|(existing, _)| existing == &shader) {
+                continue;
+            }
+            match self.compile(&shader, display) {
+                Ok(program) => {
+                    self.programs.insert(shader.clone(), program.clone());
+                    reloaded.push((shader, program));
+                }
+                Err(_) => warn!("Keeping previous program for {} + {}", shader.vertex_path, shader.fragment_path),
+            }
         }
+        reloaded
+    }
+
+    fn depends_on(&self, shader: &Shader, changed_paths: &[PathBuf]) -> bool {
+        let root_files = [PathBuf::from(&shader.vertex_path), PathBuf::from(&shader.fragment_path)];
+        let files = self.dependencies.get(shader).map_or(&root_files[..], |files| &files[..]);
+        files.iter().any(|file| changed_paths.iter().any(|path| same_file(file, path)))
+    }
+
+    fn compile(&mut self, shader: &Shader, display: &Display<WindowSurface>) -> Result<Rc<Program>> {
+        let start = Instant::now();
+        let sources = match shader.preprocess(&self.defines) {
+            Ok(sources) => sources,
+            Err(e) => {
+                error!("Couldn't preprocess program {} + {}: {}", shader.vertex_path, shader.fragment_path, e);
+                self.stats.failed += 1;
+                let dependencies = self.dependencies.entry(shader.clone()).or_insert_with(|| {
+                    vec![PathBuf::from(&shader.vertex_path), PathBuf::from(&shader.fragment_path)]
+                });
+                if !dependencies.contains(&e.path) {
+                    dependencies.push(e.path.clone());
+                }
+                self.errors.insert(shader.clone(), e.to_string());
+                return Err(Error::from(e));
+            }
+        };
+        self.dependencies.insert(shader.clone(), sources.files());
+
+        let program = match Program::from_source(display, &sources.vertex.source, &sources.fragment.source, None) {
+            Ok(program) => Rc::new(program),
+            Err(e) => {
+                let message = match &e {
+                    ProgramCreationError::CompilationError(log, ShaderType::Vertex) => sources.vertex.map_log(log),
+                    ProgramCreationError::CompilationError(log, ShaderType::Fragment) => sources.fragment.map_log(log),
+                    _ => e.to_string(),
+                };
+                error!("Couldn't compile program {} + {}: {}", shader.vertex_path, shader.fragment_path, message);
+                self.stats.failed += 1;
+                self.errors.insert(shader.clone(), message.clone());
+                return Err(Error::ShaderCompilation {
+                    vertex_path: shader.vertex_path.clone(),
+                    fragment_path: shader.fragment_path.clone(),
+                    message,
+                });
+            }
+        };
+        let elapsed = start.elapsed();
+        self.stats.compiled += 1;
+        self.stats.compile_time += elapsed;
+        self.errors.remove(shader);
+
+        info!("Compiled program {} + {} in {:.1} ms", shader.vertex_path, shader.fragment_path, elapsed.as_secs_f64() * 1000.0);
+        Ok(program)
     }
-}
\ No newline at end of file
+
+    // Releases programs no material or pass references anymore, returns how many were dropped
+    pub fn unload_unused(&mut self) -> usize {
+        let before = self.programs.len();
+        self.programs.retain(|_, program| Rc::strong_count(program) > 1);
+        // Failed programs keep their includes so fixing one of them still triggers a recompile
+        let (programs, errors) = (&self.programs, &self.errors);
+        self.dependencies.retain(|shader, _| programs.contains_key(shader) || errors.contains_key(shader));
+        before - self.programs.len()
+    }
+
+    pub fn errors(&self) -> &HashMap<Shader, String> {
+        &self.errors
+    }
+
+    pub fn stats(&self) -> ShaderCacheStats {
+        self.stats
+    }
+
+    pub fn log_stats(&self) {
+        info!(
+            "Shader cache: {} programs compiled in {:.1} ms, {} cache hits, {} failed",
+            self.stats.compiled,
+            self.stats.compile_time.as_secs_f64() * 1000.0,
+            self.stats.hits,
+            self.stats.failed,
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+}
@@ -0,0 +1,339 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Used when neither stage declares a #version
+pub const DEFAULT_GLSL_VERSION: u32 = 150;
+
+#[derive(Debug)]
+pub struct PreprocessError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+        } else {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GlslVersion {
+    pub number: u32,
+    pub profile: Option<String>,
+}
+
+impl GlslVersion {
+    fn parse(directive: &str) -> Option<GlslVersion> {
+        let mut parts = directive.split_whitespace();
+        let number = parts.next()?.parse().ok()?;
+        Some(GlslVersion {
+            number,
+            profile: parts.next().map(|profile| profile.to_string()),
+        })
+    }
+}
+
+impl Default for GlslVersion {
+    fn default() -> GlslVersion {
+        GlslVersion {
+            number: DEFAULT_GLSL_VERSION,
+            profile: None,
+        }
+    }
+}
+
+impl fmt::Display for GlslVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.profile {
+            Some(profile) => write!(f, "#version {} {}", self.number, profile),
+            None => write!(f, "#version {}", self.number),
+        }
+    }
+}
+
+// A source file with its includes expanded and #version stripped, not yet ready to compile
+pub struct ExpandedSource {
+    pub version: Option<GlslVersion>,
+    pub files: Vec<PathBuf>,
+    lines: Vec<(String, usize, usize)>,
+}
+
+impl ExpandedSource {
+    pub fn load(path: &Path) -> Result<ExpandedSource, PreprocessError> {
+        let mut expanded = ExpandedSource {
+            version: None,
+            files: vec![],
+            lines: vec![],
+        };
+        let mut stack = vec![];
+        expanded.expand(path, &mut stack, None)?;
+        Ok(expanded)
+    }
+
+    fn expand(&mut self, path: &Path, stack: &mut Vec<PathBuf>, included_from: Option<(&Path, usize)>) -> Result<(), PreprocessError> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical) {
+            let (parent, line) = included_from.unwrap_or((path, 0));
+            return Err(PreprocessError {
+                path: parent.to_path_buf(),
+                line,
+                message: format!("Include cycle through {}", path.display()),
+            });
+        }
+        // Every file is pasted at most once, so includes don't need their own guards
+        if self.files.iter().any(|file| file.canonicalize().is_ok_and(|file| file == canonical)) {
+            return Ok(());
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| {
+            let (error_path, line) = included_from.unwrap_or((path, 0));
+            PreprocessError {
+                path: error_path.to_path_buf(),
+                line,
+                message: format!("Couldn't read {}: {}", path.display(), e),
+            }
+        })?;
+        if content.trim().is_empty() {
+            return Err(PreprocessError {
+                path: path.to_path_buf(),
+                line: 0,
+                message: String::from("Shader file is empty"),
+            });
+        }
+        let file_index = self.files.len();
+        self.files.push(path.to_path_buf());
+        stack.push(canonical);
+
+        for (number, line) in content.lines().enumerate() {
+            let number = number + 1;
+            let directive = line.trim_start();
+            if let Some(version) = directive.strip_prefix("#version") {
+                let version = GlslVersion::parse(version).ok_or_else(|| PreprocessError {
+                    path: path.to_path_buf(),
+                    line: number,
+                    message: String::from("Malformed #version directive"),
+                })?;
+                if self.version.as_ref().is_none_or(|current| version.number > current.number) {
+                    self.version = Some(version);
+                }
+            } else if let Some(include) = directive.strip_prefix("#include") {
+                let include = include.trim();
+                let relative = include.strip_prefix('"').and_then(|include| include.strip_suffix('"')).ok_or_else(|| PreprocessError {
+                    path: path.to_path_buf(),
+                    line: number,
+                    message: format!("Expected #include \"file\", found #include {}", include),
+                })?;
+                let include_path = self.resolve_include(path, relative);
+                self.expand(&include_path, stack, Some((path, number)))?;
+            } else {
+                self.lines.push((line.to_string(), file_index, number));
+            }
+        }
+
+        stack.pop();
+        Ok(())
+    }
+
+    // Includes resolve next to the including file first, then next to the root shader
+    fn resolve_include(&self, including_file: &Path, relative: &str) -> PathBuf {
+        let directory = including_file.parent().unwrap_or(Path::new("."));
+        let candidate = directory.join(relative);
+        if candidate.exists() {
+            return candidate;
+        }
+        match self.files.first().and_then(|root| root.parent()) {
+            Some(root_directory) => root_directory.join(relative),
+            None => candidate,
+        }
+    }
+
+    pub fn finish(&self, version: &GlslVersion, defines: &[(String, String)]) -> PreprocessedSource {
+        let mut source = format!("{}\n", version);
+        let mut locations = vec![None];
+        for (name, value) in defines {
+            source.push_str(&format!("#define {} {}\n", name, value));
+            locations.push(None);
+        }
+        for (line, file_index, number) in &self.lines {
+            source.push_str(line);
+            source.push('\n');
+            locations.push(Some((*file_index, *number)));
+        }
+
+        PreprocessedSource {
+            source,
+            files: self.files.clone(),
+            locations,
+        }
+    }
+}
+
+// Final GLSL handed to the driver, remembers where each line came from
+pub struct PreprocessedSource {
+    pub source: String,
+    pub files: Vec<PathBuf>,
+    locations: Vec<Option<(usize, usize)>>,
+}
+
+impl PreprocessedSource {
+    // Driver line numbers start at 1
+    pub fn location(&self, line: usize) -> Option<(&Path, usize)> {
+        let (file_index, number) = (*self.locations.get(line.checked_sub(1)?)?)?;
+        Some((self.files[file_index].as_path(), number))
+    }
+
+    // Rewrites the "0(12)" and "0:12" locations drivers put in compile logs to the original file and line
+    pub fn map_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.map_log_line(line))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn map_log_line(&self, text: &str) -> String {
+        let bytes = text.as_bytes();
+        for start in 0..bytes.len() {
+            if !bytes[start].is_ascii_digit() || (start > 0 && bytes[start - 1].is_ascii_alphanumeric()) {
+                continue;
+            }
+            let mut separator = start;
+            while separator < bytes.len() && bytes[separator].is_ascii_digit() {
+                separator += 1;
+            }
+            if separator >= bytes.len() || (bytes[separator] != b'(' && bytes[separator] != b':') {
+                continue;
+            }
+            let number_start = separator + 1;
+            let mut number_end = number_start;
+            while number_end < bytes.len() && bytes[number_end].is_ascii_digit() {
+                number_end += 1;
+            }
+            if number_end == number_start {
+                continue;
+            }
+            let end = if bytes[separator] == b'(' {
+                if number_end >= bytes.len() || bytes[number_end] != b')' {
+                    continue;
+                }
+                number_end + 1
+            } else {
+                number_end
+            };
+
+            let line: usize = match text[number_start..number_end].parse() {
+                Ok(line) => line,
+                Err(_) => continue,
+            };
+            if let Some((path, number)) = self.location(line) {
+                return format!("{}{}:{}{}", &text[..start], path.display(), number, &text[end..]);
+            }
+            break;
+        }
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fresh directory under the system temp directory holding the given files
+    fn shader_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("hexgen_preprocessor_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for (file, content) in files {
+            fs::write(directory.join(file), content).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn includes_are_expanded_once() {
+        let directory = shader_dir("includes", &[
+            ("main.frag", "#version 140\n#include \"a.glsl\"\n#include \"b.glsl\"\nvoid main() {}\n"),
+            ("a.glsl", "#include \"common.glsl\"\nfloat a;\n"),
+            ("b.glsl", "#include \"common.glsl\"\nfloat b;\n"),
+            ("common.glsl", "float common;\n"),
+        ]);
+        let expanded = ExpandedSource::load(&directory.join("main.frag")).unwrap();
+        assert_eq!(expanded.files.len(), 4);
+        let source = expanded.finish(&GlslVersion::default(), &[]).source;
+        assert_eq!(source, "#version 150\nfloat common;\nfloat a;\nfloat b;\nvoid main() {}\n");
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn include_cycle_reports_file_and_line() {
+        let directory = shader_dir("cycle", &[
+            ("main.frag", "#version 140\n#include \"a.glsl\"\n"),
+            ("a.glsl", "float a;\n#include \"b.glsl\"\n"),
+            ("b.glsl", "\n\n#include \"a.glsl\"\n"),
+        ]);
+        let error = match ExpandedSource::load(&directory.join("main.frag")) {
+            Err(error) => error,
+            Ok(_) => panic!("the include cycle wasn't detected"),
+        };
+        assert_eq!(error.path, directory.join("b.glsl"));
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("Include cycle"));
+        assert!(error.to_string().starts_with(&format!("{}:3:", directory.join("b.glsl").display())));
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn highest_version_wins() {
+        let directory = shader_dir("version", &[
+            ("main.vert", "#version 140\n#include \"lighting.glsl\"\nvoid main() {}\n"),
+            ("lighting.glsl", "#version 330 core\nfloat light;\n"),
+        ]);
+        let expanded = ExpandedSource::load(&directory.join("main.vert")).unwrap();
+        assert_eq!(expanded.version, Some(GlslVersion { number: 330, profile: Some(String::from("core")) }));
+        assert_eq!(expanded.version.unwrap().to_string(), "#version 330 core");
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn defines_follow_version() {
+        let directory = shader_dir("defines", &[("main.frag", "// comment\n#version 140\nvoid main() {}\n")]);
+        let expanded = ExpandedSource::load(&directory.join("main.frag")).unwrap();
+        let defines = [(String::from("MAX_LIGHTS"), String::from("8")), (String::from("SHADOWS"), String::from("1"))];
+        let preprocessed = expanded.finish(expanded.version.as_ref().unwrap(), &defines);
+        assert_eq!(preprocessed.source, "#version 140\n#define MAX_LIGHTS 8\n#define SHADOWS 1\n// comment\nvoid main() {}\n");
+        // Generated lines have no source location, the rest keep theirs
+        assert_eq!(preprocessed.location(1), None);
+        assert_eq!(preprocessed.location(3), None);
+        assert_eq!(preprocessed.location(5), Some((directory.join("main.frag").as_path(), 3)));
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn log_locations_map_to_original_lines() {
+        let directory = shader_dir("log", &[
+            ("main.frag", "#version 140\n#include \"common.glsl\"\nvoid main() {\n    broken;\n}\n"),
+            ("common.glsl", "float a;\nfloat b;\n"),
+        ]);
+        let expanded = ExpandedSource::load(&directory.join("main.frag")).unwrap();
+        let preprocessed = expanded.finish(&GlslVersion::default(), &[]);
+        let main = directory.join("main.frag");
+        let common = directory.join("common.glsl");
+
+        // Line 5 of the final source is line 4 of main.frag, line 3 is line 2 of common.glsl
+        assert_eq!(preprocessed.map_log("0(5) : error C1008: undefined variable \"broken\""),
+                   format!("{}:4 : error C1008: undefined variable \"broken\"", main.display()));
+        assert_eq!(preprocessed.map_log("ERROR: 0:3: 'b' : redefinition"),
+                   format!("ERROR: {}:2: 'b' : redefinition", common.display()));
+        assert_eq!(preprocessed.map_log("0(5) : error\nWARNING: 0:3: unused"),
+                   format!("{}:4 : error\nWARNING: {}:2: unused", main.display(), common.display()));
+        // Out of range lines and plain text stay as they are
+        assert_eq!(preprocessed.map_log("0(99) : error"), "0(99) : error");
+        assert_eq!(preprocessed.map_log("link failed"), "link failed");
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
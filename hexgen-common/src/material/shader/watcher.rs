@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{error, info};
+
+// Watches the shader directory so changed sources can be recompiled without restarting
+pub struct ShaderWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl ShaderWatcher {
+    pub fn new(directory: &str) -> Option<ShaderWatcher> {
+        let (sender, events) = channel();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                error!("Couldn't create shader watcher: {}", e);
+                return None;
+            }
+        };
+        if let Err(e) = watcher.watch(Path::new(directory), RecursiveMode::Recursive) {
+            error!("Couldn't watch shader directory {}: {}", directory, e);
+            return None;
+        }
+
+        info!("Watching shader directory {}", directory);
+        Some(ShaderWatcher {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    // Drains every pending event, editors often emit several per save so paths are deduplicated
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = vec![];
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                        for path in event.paths {
+                            if !paths.contains(&path) {
+                                paths.push(path);
+                            }
+                        }
+                    }
+                }
+                Err(e) => error!("Shader watcher error: {}", e),
+            }
+        }
+        paths
+    }
+}
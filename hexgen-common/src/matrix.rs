@@ -1,6 +1,10 @@
 use std::ops;
+use crate::vector3::Vector3;
+use crate::vector4::Vector4;
 
-#[derive(Debug)]
+// Stored the way glium uploads it, matrix.0[3] holds the translation.
+// Vectors are rows multiplied on the left, so a * b applies a first and then b
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Matrix(pub [[f32; 4]; 4]);
 
 impl ops::Add<Matrix> for Matrix {
@@ -71,14 +75,281 @@ impl ops::Mul<Matrix> for Matrix {
 
 impl ops::MulAssign<Matrix> for Matrix {
     fn mul_assign(&mut self, rhs: Matrix) {
-        for i in 0..4 {
+        *self = *self * rhs;
+    }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Matrix::identity()
+    }
+}
+
+impl Matrix {
+    pub fn identity() -> Matrix {
+        Matrix([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(translation: Vector3) -> Matrix {
+        let mut matrix = Matrix::identity();
+        matrix.0[3] = [translation.x, translation.y, translation.z, 1.0];
+        matrix
+    }
+
+    pub fn scaling(scale: Vector3) -> Matrix {
+        Matrix([
+            [scale.x, 0.0, 0.0, 0.0],
+            [0.0, scale.y, 0.0, 0.0],
+            [0.0, 0.0, scale.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // View matrix for an eye at position looking along direction, both axes don't need to be normalized
+    pub fn look_to(position: Vector3, direction: Vector3, up: Vector3) -> Matrix {
+        let forward = direction.normalize();
+        let side = up.cross(forward).normalize();
+        let up = forward.cross(side);
+
+        Matrix([
+            [side.x, up.x, forward.x, 0.0],
+            [side.y, up.y, forward.y, 0.0],
+            [side.z, up.z, forward.z, 0.0],
+            [-position.dot(side), -position.dot(up), -position.dot(forward), 1.0],
+        ])
+    }
+
+    pub fn look_at(position: Vector3, target: Vector3, up: Vector3) -> Matrix {
+        Matrix::look_to(position, target - position, up)
+    }
+
+    // Left-handed, view space depth maps to -1..1 between z_near and z_far. Aspect ratio is width / height
+    pub fn perspective(fov: f32, aspect_ratio: f32, z_near: f32, z_far: f32) -> Matrix {
+        let f = 1.0 / (fov / 2.0).tan();
+
+        Matrix([
+            [f / aspect_ratio, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, (z_far + z_near) / (z_far - z_near), 1.0],
+            [0.0, 0.0, -(2.0 * z_far * z_near) / (z_far - z_near), 0.0],
+        ])
+    }
+
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, z_near: f32, z_far: f32) -> Matrix {
+        Matrix([
+            [2.0 / (right - left), 0.0, 0.0, 0.0],
+            [0.0, 2.0 / (top - bottom), 0.0, 0.0],
+            [0.0, 0.0, 2.0 / (z_far - z_near), 0.0],
+            [-(right + left) / (right - left), -(top + bottom) / (top - bottom), -(z_far + z_near) / (z_far - z_near), 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut matrix = [[0f32; 4]; 4];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.0[j][i];
+            }
+        }
+        Matrix(matrix)
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.0;
+        let minor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+
+        // Laplace expansion over the 2x2 minors of the first two and last two rows
+        minor(0, 1, 0, 1) * minor(2, 3, 2, 3)
+            - minor(0, 1, 0, 2) * minor(2, 3, 1, 3)
+            + minor(0, 1, 0, 3) * minor(2, 3, 1, 2)
+            + minor(0, 1, 1, 2) * minor(2, 3, 0, 3)
+            - minor(0, 1, 1, 3) * minor(2, 3, 0, 2)
+            + minor(0, 1, 2, 3) * minor(2, 3, 0, 1)
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None when the matrix is singular
+    pub fn inverse(&self) -> Option<Matrix> {
+        let mut m = self.0;
+        let mut inverse = Matrix::identity().0;
+
+        for column in 0..4 {
+            let pivot = (column..4).max_by(|a, b| m[*a][column].abs().total_cmp(&m[*b][column].abs()))?;
+            if m[pivot][column].abs() <= f32::EPSILON {
+                return None;
+            }
+            m.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / m[column][column];
             for j in 0..4 {
-                let mut value = 0f32;
-                for k in 0..4 {
-                    value += self.0[i][k] * rhs.0[k][j];
+                m[column][j] *= scale;
+                inverse[column][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+                let factor = m[row][column];
+                for j in 0..4 {
+                    m[row][j] -= factor * m[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
                 }
-                self.0[i][j] = value;
             }
         }
+        Some(Matrix(inverse))
+    }
+
+    pub fn transform(&self, vector: Vector4) -> Vector4 {
+        let m = &self.0;
+        Vector4::new(
+            vector.x * m[0][0] + vector.y * m[1][0] + vector.z * m[2][0] + vector.w * m[3][0],
+            vector.x * m[0][1] + vector.y * m[1][1] + vector.z * m[2][1] + vector.w * m[3][1],
+            vector.x * m[0][2] + vector.y * m[1][2] + vector.z * m[2][2] + vector.w * m[3][2],
+            vector.x * m[0][3] + vector.y * m[1][3] + vector.z * m[2][3] + vector.w * m[3][3],
+        )
+    }
+
+    // Includes the translation and the perspective divide
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        self.transform(Vector4::point(point)).perspective_divide()
+    }
+
+    // Ignores the translation
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        self.transform(Vector4::direction(vector)).xyz()
+    }
+
+    pub fn approx_eq(&self, rhs: &Matrix, epsilon: f32) -> bool {
+        self.0.iter().flatten().zip(rhs.0.iter().flatten()).all(|(a, b)| (a - b).abs() <= epsilon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn sample() -> Matrix {
+        Matrix([
+            [2.0, 0.0, 1.0, 3.0],
+            [1.0, 3.0, 2.0, 0.0],
+            [0.0, 1.0, 4.0, 1.0],
+            [5.0, 2.0, 0.0, 1.0],
+        ])
+    }
+
+    #[test]
+    fn identity_is_neutral() {
+        assert_eq!(sample() * Matrix::identity(), sample());
+        assert_eq!(Matrix::identity() * sample(), sample());
+        assert_eq!(Matrix::default(), Matrix::identity());
+    }
+
+    #[test]
+    fn multiply_matches_reference() {
+        let b = Matrix([
+            [1.0, 2.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 3.0],
+            [4.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 2.0, 1.0],
+        ]);
+        let expected = Matrix([
+            [6.0, 4.0, 7.0, 3.0],
+            [9.0, 5.0, 2.0, 9.0],
+            [16.0, 1.0, 6.0, 4.0],
+            [5.0, 12.0, 2.0, 7.0],
+        ]);
+        assert_eq!(sample() * b, expected);
+
+        let mut c = sample();
+        c *= b;
+        assert_eq!(c, expected);
+    }
+
+    #[test]
+    fn transpose() {
+        let transposed = sample().transpose();
+        assert_eq!(transposed.0[0], [2.0, 1.0, 0.0, 5.0]);
+        assert_eq!(transposed.0[3], [3.0, 0.0, 1.0, 1.0]);
+        assert_eq!(transposed.transpose(), sample());
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(Matrix::identity().determinant(), 1.0);
+        assert_eq!(sample().determinant(), -84.0);
+        assert_eq!(Matrix::scaling(Vector3::new(2.0, 3.0, 4.0)).determinant(), 24.0);
+        assert!((sample().transpose().determinant() + 84.0).abs() <= EPSILON);
+    }
+
+    #[test]
+    fn inverse() {
+        let expected = Matrix([
+            [-1.0 / 6.0, -23.0 / 84.0, 5.0 / 28.0, 9.0 / 28.0],
+            [1.0 / 6.0, 47.0 / 84.0, -9.0 / 28.0, -5.0 / 28.0],
+            [-1.0 / 6.0, -17.0 / 84.0, 11.0 / 28.0, 3.0 / 28.0],
+            [0.5, 0.25, -0.25, -0.25],
+        ]);
+        let inverse = sample().inverse().unwrap();
+        assert!(inverse.approx_eq(&expected, EPSILON));
+        assert!((sample() * inverse).approx_eq(&Matrix::identity(), EPSILON));
+        assert!((inverse * sample()).approx_eq(&Matrix::identity(), EPSILON));
+
+        let mut singular = sample();
+        singular.0[3] = singular.0[0];
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn transforms() {
+        let translation = Vector3::new(1.0, 2.0, 3.0);
+        let matrix = Matrix::scaling(Vector3::new(2.0, 2.0, 2.0)) * Matrix::translation(translation);
+        let point = Vector3::new(1.0, 0.0, -1.0);
+        assert_eq!(matrix.transform_point(point), Vector3::new(3.0, 2.0, 1.0));
+        assert_eq!(matrix.transform_vector(point), Vector3::new(2.0, 0.0, -2.0));
+        assert_eq!(matrix.transform(Vector4::point(point)), Vector4::new(3.0, 2.0, 1.0, 1.0));
+        assert!(matrix.inverse().unwrap().transform_point(Vector3::new(3.0, 2.0, 1.0)).approx_eq(point, EPSILON));
+    }
+
+    #[test]
+    fn look_to_matches_reference() {
+        // Camera at (0, 5, -5) looking down 45 degrees towards the origin
+        let view = Matrix::look_to(Vector3::new(0.0, 5.0, -5.0), Vector3::new(0.0, -1.0, 1.0), Vector3::up());
+        let h = std::f32::consts::FRAC_1_SQRT_2;
+        let expected = Matrix([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, h, -h, 0.0],
+            [0.0, h, h, 0.0],
+            [0.0, 0.0, 10.0 * h, 1.0],
+        ]);
+        assert!(view.approx_eq(&expected, EPSILON));
+        assert!(view.transform_point(Vector3::zero()).approx_eq(Vector3::new(0.0, 0.0, 10.0 * h), EPSILON));
+
+        let at = Matrix::look_at(Vector3::new(0.0, 5.0, -5.0), Vector3::zero(), Vector3::up());
+        assert!(at.approx_eq(&view, EPSILON));
+    }
+
+    #[test]
+    fn perspective_maps_clip_planes() {
+        let projection = Matrix::perspective(std::f32::consts::FRAC_PI_2, 2.0, 0.1, 100.0);
+        assert!((projection.0[0][0] - 0.5).abs() <= EPSILON);
+        assert!((projection.0[1][1] - 1.0).abs() <= EPSILON);
+        assert!((projection.transform_point(Vector3::new(0.0, 0.0, 0.1)).z + 1.0).abs() <= EPSILON);
+        assert!((projection.transform_point(Vector3::new(0.0, 0.0, 100.0)).z - 1.0).abs() <= 1e-4);
+        assert!(projection.transform_point(Vector3::new(10.0, 5.0, 5.0)).approx_eq(Vector3::new(1.0, 1.0, projection.transform_point(Vector3::new(0.0, 0.0, 5.0)).z), EPSILON));
+    }
+
+    #[test]
+    fn orthographic_maps_box_to_cube() {
+        let projection = Matrix::orthographic(-4.0, 4.0, -2.0, 2.0, 0.5, 10.5);
+        assert!(projection.transform_point(Vector3::new(-4.0, -2.0, 0.5)).approx_eq(Vector3::new(-1.0, -1.0, -1.0), EPSILON));
+        assert!(projection.transform_point(Vector3::new(4.0, 2.0, 10.5)).approx_eq(Vector3::one(), EPSILON));
+        assert!(projection.transform_point(Vector3::new(0.0, 0.0, 5.5)).approx_eq(Vector3::zero(), EPSILON));
     }
 }
\ No newline at end of file
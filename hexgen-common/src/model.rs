@@ -1,8 +1,20 @@
-use glium::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use glium::{Display, Program};
 use glium::glutin::surface::WindowSurface;
 use russimp::scene::{PostProcess, Scene};
 use tracing::info;
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::material::overrides::MaterialOverrides;
+use crate::material::shader::{Shader, ShaderCache, DIFFUSE_FRAGMENT_SHADER, DIFFUSE_VERTEX_SHADER};
 use crate::model::mesh::Mesh;
+use crate::model::mesh::normal::Normal;
+use crate::model::mesh::tangent::Tangent;
+use crate::model::mesh::texture::TextureCache;
+use crate::model::mesh::vertex::Vertex;
+use crate::vector3::Vector3;
 
 pub mod mesh;
 
@@ -19,29 +31,138 @@ impl Model {
         }
     }
 
-    pub fn load_model(&mut self, file_path: &str, display: &Display<WindowSurface>) {
+    pub fn load_model(&mut self, file_path: &str, display: &Display<WindowSurface>, texture_cache: &mut TextureCache, shader_cache: &mut ShaderCache) -> Result<()> {
+        let metadata = fs::metadata(file_path).map_err(|source| Error::Io {
+            path: PathBuf::from(file_path),
+            source,
+        })?;
+        if metadata.len() == 0 {
+            return Err(Error::EmptyFile { path: PathBuf::from(file_path) });
+        }
+
         let scene = Scene::from_file(file_path,
                                      vec![PostProcess::CalculateTangentSpace,
                                           PostProcess::Triangulate,
                                           PostProcess::JoinIdenticalVertices,
-                                          PostProcess::SortByPrimitiveType]).unwrap_or_else(|_| { panic!("Couldn't load provided model") });
+                                          PostProcess::SortByPrimitiveType]).map_err(|e| Error::ModelImport {
+            path: PathBuf::from(file_path),
+            message: e.to_string(),
+        })?;
+        if scene.meshes.is_empty() {
+            return Err(Error::ModelImport {
+                path: PathBuf::from(file_path),
+                message: String::from("scene contains no meshes"),
+            });
+        }
 
         info!("Loaded scene from filepath {}", file_path);
+        let model_directory = Path::new(file_path).parent().unwrap_or(Path::new("."));
         for mesh in scene.meshes {
-            self.meshes.push(Mesh::load_mesh(mesh, scene.materials.as_ref()));
-            self.meshes.last_mut().unwrap().set_normals_buffer(display);
-            self.meshes.last_mut().unwrap().set_indices_buffer(display);
-            self.meshes.last_mut().unwrap().set_vertex_positions_buffer(display);
+            let mut mesh = Mesh::load_mesh(mesh, scene.materials.as_ref(), model_directory, texture_cache, display);
+            mesh.set_buffers(display)?;
+            self.meshes.push(mesh);
         }
         info!("Loaded model '{}'", self.name);
-        self.load_materials(display);
+        self.load_materials(display, shader_cache)
+    }
+
+    // Wraps meshes built in code, their buffers must already be uploaded
+    pub fn from_meshes(name: String, meshes: Vec<Mesh>, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<Model> {
+        let mut model = Model::new(name);
+        model.meshes = meshes;
+        model.load_materials(display, shader_cache)?;
+        Ok(model)
+    }
+
+    // Bright magenta box drawn in place of models that failed to load
+    pub fn missing(display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<Model> {
+        let mut mesh = Mesh::new();
+        let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+            ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]),
+            ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+            ([0.0, 1.0, 0.0], [0.0, 0.0, -1.0], [1.0, 0.0, 0.0]),
+            ([0.0, -1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]),
+            ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+            ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+        ];
+        // Faces are (normal, up, right) with right x up = normal so they wind counter clockwise
+        let half_extent = 0.35;
+        for (normal, up, right) in faces {
+            let first = mesh.vertices.len() as u32;
+            for (u, v) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let corner = |axis: usize| (normal[axis] + right[axis] * u + up[axis] * v) * half_extent;
+                // Sits on the tile surface like the terrain models
+                mesh.add_vertex(Vertex::new((corner(0), corner(1) + half_extent, corner(2)), ((u + 1.0) / 2.0, (v + 1.0) / 2.0)));
+                mesh.add_normal(Normal::new((normal[0], normal[1], normal[2])));
+                mesh.add_tangent(Tangent::new((right[0], right[1], right[2])));
+            }
+            for index in [0, 1, 2, 0, 2, 3] {
+                mesh.add_index(first + index);
+            }
+        }
+        mesh.set_buffers(display)?;
+
+        let magenta = Vector3::new(1.0, 0.0, 1.0);
+        let mut material = Material::new(String::from("missing"), Shader::new(DIFFUSE_VERTEX_SHADER, DIFFUSE_FRAGMENT_SHADER), magenta * 0.2, magenta, Vector3::zero());
+        material.emissive_color = magenta * 0.4;
+        mesh.material = Some(material);
+
+        Model::from_meshes(String::from("Missing model"), vec![mesh], display, shader_cache)
+    }
+
+    pub fn set_material_shader(&mut self, material_name: &str, shader: &Shader, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<()> {
+        for mesh in &mut self.meshes {
+            if let Some(ref mut material) = &mut mesh.material {
+                if material.name == material_name {
+                    material.set_shader(shader.clone(), display, shader_cache)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply_material_overrides(&mut self, material_overrides: &MaterialOverrides, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<()> {
+        for mesh in &mut self.meshes {
+            if let Some(ref mut material) = &mut mesh.material {
+                if let Some(material_override) = material_overrides.get(&material.name) {
+                    material.apply_override(material_override, display, shader_cache)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn swap_programs(&mut self, reloaded: &[(Shader, Rc<Program>)]) {
+        for mesh in &mut self.meshes {
+            if let Some(ref mut material) = &mut mesh.material {
+                material.swap_program(reloaded);
+            }
+        }
+    }
+
+    // Local space bounding box of every mesh, None for an empty model
+    pub fn bounds(&self) -> Option<(Vector3, Vector3)> {
+        let mut positions = self.meshes.iter().flat_map(|mesh| mesh.vertices.iter().map(|vertex| {
+            let (x, y, z) = vertex.position();
+            Vector3::new(x, y, z)
+        }));
+        let first = positions.next()?;
+        Some(positions.fold((first, first), |(min, max), position| (
+            min.min(position),
+            max.max(position),
+        )))
+    }
+
+    pub fn has_transparent_meshes(&self) -> bool {
+        self.meshes.iter().any(|mesh| mesh.material.as_ref().is_some_and(|material| material.is_transparent()))
     }
 
-    fn load_materials(&mut self, display: &Display<WindowSurface>){
+    fn load_materials(&mut self, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<()> {
         for mesh in &mut self.meshes {
             if let Some(ref mut material) = &mut mesh.material {
-                material.load_material(display);
+                material.load_material(display, shader_cache)?;
             }
         }
+        Ok(())
     }
 }
@@ -1,27 +1,34 @@
+use std::path::Path;
 use glium::glutin::surface::WindowSurface;
 use glium::{Display, IndexBuffer, VertexBuffer};
-use russimp::material::PropertyTypeInfo;
+use russimp::material::{PropertyTypeInfo, TextureType};
 use normal::Normal;
-use texture::Texture;
-use tracing::info;
+use tangent::Tangent;
+use texture::{Texture, TextureCache, TextureKind};
+use tracing::{error, info};
 use vertex::Vertex;
-use crate::material::Material;
-use crate::material::shader::Shader;
+use crate::material::{Material, DEFAULT_SHININESS};
+use crate::error::{Error, Result};
+use crate::material::shader::{Shader, DIFFUSE_FRAGMENT_SHADER, DIFFUSE_VERTEX_SHADER};
 use crate::vector3::Vector3;
 
+pub mod builder;
 pub mod normal;
+pub mod tangent;
 pub mod texture;
 pub mod vertex;
 
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub normals: Vec<Normal>,
+    pub tangents: Vec<Tangent>,
     pub indices: Vec<u32>,
     pub textures: Vec<Texture>,
     pub material: Option<Material>,
     pub vertex_buffer: Option<VertexBuffer<Vertex>>,
     pub index_buffer: Option<IndexBuffer<u32>>,
     pub normals_buffer: Option<VertexBuffer<Normal>>,
+    pub tangents_buffer: Option<VertexBuffer<Tangent>>,
 }
 
 impl Mesh {
@@ -29,16 +36,18 @@ impl Mesh {
         Mesh {
             vertices: vec![],
             normals: vec![],
+            tangents: vec![],
             indices: vec![],
             textures: vec![],
             material: None,
             vertex_buffer: None,
             index_buffer: None,
             normals_buffer: None,
+            tangents_buffer: None,
         }
     }
 
-    pub fn load_mesh(ai_mesh: russimp::mesh::Mesh, materials: &Vec<russimp::material::Material>) -> Mesh {
+    pub fn load_mesh(ai_mesh: russimp::mesh::Mesh, materials: &Vec<russimp::material::Material>, model_directory: &Path, texture_cache: &mut TextureCache, display: &Display<WindowSurface>) -> Mesh {
         let mut mesh = Mesh::new();
 
         for i in 0..ai_mesh.vertices.len() {
@@ -59,8 +68,15 @@ impl Mesh {
                 ai_mesh.normals[i].z,
             ));
 
+            // Tangents only exist for meshes with texture coordinates
+            let tangent = match ai_mesh.tangents.get(i) {
+                Some(t) => Tangent::new((t.x, t.y, t.z)),
+                None => Tangent::new((1.0, 0.0, 0.0)),
+            };
+
             mesh.add_vertex(vertex);
             mesh.add_normal(normal);
+            mesh.add_tangent(tangent);
         }
 
         for face in ai_mesh.faces {
@@ -76,6 +92,11 @@ impl Mesh {
             let mut mat_diffuse: Vector3 = Vector3::zero();
             let mut mat_specular: Vector3 = Vector3::zero();
             let mut mat_ambient: Vector3 = Vector3::zero();
+            let mut mat_emissive: Vector3 = Vector3::zero();
+            let mut mat_shininess: f32 = DEFAULT_SHININESS;
+            let mut mat_opacity: Option<f32> = None;
+            let mut mat_transparency: Option<f32> = None;
+            let mut texture_paths: Vec<(String, TextureKind)> = vec![];
 
             for prop in &mat.properties {
                 if prop.key == "?mat.name" {
@@ -94,11 +115,67 @@ impl Mesh {
                     if let PropertyTypeInfo::FloatArray(arr) = &prop.data {
                         mat_specular = Vector3::new(arr[0], arr[1], arr[2]);
                     }
+                } else if prop.key == "$clr.emissive" {
+                    if let PropertyTypeInfo::FloatArray(arr) = &prop.data {
+                        mat_emissive = Vector3::new(arr[0], arr[1], arr[2]);
+                    }
+                } else if prop.key == "$mat.shininess" {
+                    // OBJ files without Ns still report a shininess of 0
+                    if let PropertyTypeInfo::FloatArray(arr) = &prop.data {
+                        if arr[0] > 0.0 {
+                            mat_shininess = arr[0];
+                        }
+                    }
+                } else if prop.key == "$mat.opacity" {
+                    if let PropertyTypeInfo::FloatArray(arr) = &prop.data {
+                        mat_opacity = Some(arr[0]);
+                    }
+                } else if prop.key == "$mat.transparencyfactor" {
+                    if let PropertyTypeInfo::FloatArray(arr) = &prop.data {
+                        mat_transparency = Some(arr[0]);
+                    }
+                } else if prop.key == "$tex.file" {
+                    if let PropertyTypeInfo::String(path) = &prop.data {
+                        // MTL files reference bump maps through map_Bump, which assimp reports as height
+                        let kind = match prop.semantic {
+                            TextureType::Diffuse => Some(TextureKind::Diffuse),
+                            TextureType::Normals | TextureType::Height => Some(TextureKind::Normal),
+                            _ => None,
+                        };
+                        if let Some(kind) = kind {
+                            texture_paths.push((path.replace('\\', "/"), kind));
+                        }
+                    }
+                }
+            }
+
+            for (path, kind) in texture_paths {
+                let full_path = model_directory.join(&path);
+                let full_path = full_path.to_string_lossy();
+                // A missing texture falls back to the renderer's default one instead of failing the mesh
+                match texture_cache.load(&full_path, display) {
+                    Ok(texture) => mesh.add_texture(Texture::new(full_path.to_string(), kind, texture)),
+                    Err(e) => error!("{}", e),
                 }
             }
 
-            let shader = Shader::new("./res/shaders/diffuse.vert", "./res/shaders/diffuse.frag");
-            mesh.material = Some(Material::new(mat_name, shader, mat_ambient, mat_diffuse, mat_specular));
+            let shader = Shader::new(DIFFUSE_VERTEX_SHADER, DIFFUSE_FRAGMENT_SHADER);
+            let mut material = Material::new(mat_name, shader, mat_ambient, mat_diffuse, mat_specular);
+            material.emissive_color = mat_emissive;
+            material.shininess = mat_shininess;
+            // d takes precedence, Tr is its inverse
+            material.opacity = match (mat_opacity, mat_transparency) {
+                (Some(opacity), _) => opacity,
+                (None, Some(transparency)) => 1.0 - transparency,
+                (None, None) => 1.0,
+            }.clamp(0.0, 1.0);
+            for texture in &mesh.textures {
+                match texture.kind {
+                    TextureKind::Diffuse => material.diffuse_texture = Some(texture.texture.clone()),
+                    TextureKind::Normal => material.normal_texture = Some(texture.texture.clone()),
+                }
+            }
+            mesh.material = Some(material);
         }
 
         info!("Loaded mesh");
@@ -113,6 +190,10 @@ impl Mesh {
         self.normals.push(normal);
     }
 
+    pub fn add_tangent(&mut self, tangent: Tangent) {
+        self.tangents.push(tangent);
+    }
+
     pub fn add_index(&mut self, index: u32) {
         self.indices.push(index);
     }
@@ -124,20 +205,43 @@ impl Mesh {
     pub fn set_vertex_positions_buffer(
         &mut self,
         display: &Display<WindowSurface>,
-    ) {
-        self.vertex_buffer = Some(VertexBuffer::new(display, &self.vertices).unwrap());
+    ) -> Result<()> {
+        self.vertex_buffer = Some(VertexBuffer::new(display, &self.vertices).map_err(|e| buffer_error("vertex", e))?);
+        Ok(())
+    }
+
+    pub fn set_normals_buffer(&mut self, display: &Display<WindowSurface>) -> Result<()> {
+        self.normals_buffer = Some(VertexBuffer::new(display, &self.normals).map_err(|e| buffer_error("normal", e))?);
+        Ok(())
     }
 
-    pub fn set_normals_buffer(&mut self, display: &Display<WindowSurface>) {
-        self.normals_buffer = Some(VertexBuffer::new(display, &self.normals).unwrap());
+    pub fn set_tangents_buffer(&mut self, display: &Display<WindowSurface>) -> Result<()> {
+        self.tangents_buffer = Some(VertexBuffer::new(display, &self.tangents).map_err(|e| buffer_error("tangent", e))?);
+        Ok(())
     }
 
-    pub fn set_indices_buffer(&mut self, display: &Display<WindowSurface>) {
+    pub fn set_indices_buffer(&mut self, display: &Display<WindowSurface>) -> Result<()> {
         self.index_buffer = Some(IndexBuffer::new(
             display,
             glium::index::PrimitiveType::TrianglesList,
             &self.indices,
         )
-            .unwrap());
+            .map_err(|e| buffer_error("index", e))?);
+        Ok(())
+    }
+
+    // Uploads every buffer the renderer draws with
+    pub fn set_buffers(&mut self, display: &Display<WindowSurface>) -> Result<()> {
+        self.set_normals_buffer(display)?;
+        self.set_tangents_buffer(display)?;
+        self.set_indices_buffer(display)?;
+        self.set_vertex_positions_buffer(display)
+    }
+}
+
+fn buffer_error(kind: &'static str, error: impl std::fmt::Display) -> Error {
+    Error::Buffer {
+        kind,
+        message: error.to_string(),
     }
 }
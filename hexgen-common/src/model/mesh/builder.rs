@@ -0,0 +1,281 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+use glium::glutin::surface::WindowSurface;
+use glium::texture::RawImage2d;
+use glium::{Display, Texture2d};
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::material::shader::{Shader, ShaderCache, DIFFUSE_FRAGMENT_SHADER, DIFFUSE_VERTEX_SHADER};
+use crate::model::Model;
+use crate::model::mesh::Mesh;
+use crate::model::mesh::normal::Normal;
+use crate::model::mesh::tangent::Tangent;
+use crate::model::mesh::vertex::Vertex;
+use crate::vector3::Vector3;
+
+// Distance from the centre of a tile to its flat sides, matches the OBJ tiles
+pub const HEX_APOTHEM: f32 = 0.5;
+
+// Collects geometry for a single mesh, triangles are wound counter clockwise seen from the front
+pub struct MeshBuilder {
+    positions: Vec<Vector3>,
+    normals: Vec<Vector3>,
+    tangents: Vec<Vector3>,
+    tex_coords: Vec<(f32, f32)>,
+    indices: Vec<u32>,
+}
+
+impl Default for MeshBuilder {
+    fn default() -> Self {
+        MeshBuilder::new()
+    }
+}
+
+impl MeshBuilder {
+    pub fn new() -> MeshBuilder {
+        MeshBuilder {
+            positions: vec![],
+            normals: vec![],
+            tangents: vec![],
+            tex_coords: vec![],
+            indices: vec![],
+        }
+    }
+
+    pub fn add_vertex(&mut self, position: Vector3, normal: Vector3, tangent: Vector3, tex_coords: (f32, f32)) -> u32 {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.tangents.push(tangent);
+        self.tex_coords.push(tex_coords);
+        self.positions.len() as u32 - 1
+    }
+
+    pub fn add_triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend_from_slice(&[a, b, c]);
+    }
+
+    pub fn add_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.add_triangle(a, b, c);
+        self.add_triangle(a, c, d);
+    }
+
+    // Replaces every normal with the area weighted average of the faces around it
+    pub fn smooth_normals(&mut self) {
+        let mut normals = vec![Vector3::zero(); self.positions.len()];
+        for triangle in self.indices.chunks(3) {
+            let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+            let face_normal = (self.positions[b] - self.positions[a]).cross(self.positions[c] - self.positions[a]);
+            for index in [a, b, c] {
+                normals[index] += face_normal;
+            }
+        }
+        self.normals = normals.into_iter().map(|normal| normal.normalize_or(Vector3::up())).collect();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn build(self, material: Material, display: &Display<WindowSurface>) -> Result<Mesh> {
+        let mut mesh = Mesh::new();
+        for i in 0..self.positions.len() {
+            let (position, normal, tangent) = (self.positions[i], self.normals[i], self.tangents[i]);
+            mesh.add_vertex(Vertex::new((position.x, position.y, position.z), self.tex_coords[i]));
+            mesh.add_normal(Normal::new((normal.x, normal.y, normal.z)));
+            mesh.add_tangent(Tangent::new((tangent.x, tangent.y, tangent.z)));
+        }
+        for index in self.indices {
+            mesh.add_index(index);
+        }
+        mesh.set_buffers(display)?;
+        mesh.material = Some(material);
+        Ok(mesh)
+    }
+}
+
+// A hexagonal column standing on y = 0, with flat sides facing the neighbour directions at i * 60 degrees.
+// The bevel chamfers the top rim and is coloured like the top
+pub struct HexPrism {
+    pub name: String,
+    pub height: f32,
+    pub bevel: f32,
+    pub top_color: Vector3,
+    pub side_color: Vector3,
+}
+
+impl HexPrism {
+    pub fn new(name: String, height: f32, top_color: Vector3, side_color: Vector3) -> HexPrism {
+        HexPrism {
+            name,
+            height,
+            bevel: 0.02,
+            top_color,
+            side_color,
+        }
+    }
+
+    pub fn build(&self, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<Model> {
+        let (top, side) = self.build_meshes();
+        // The top material carries the prism's name so material overrides can target it
+        let mut meshes = vec![top.build(colored_material(self.name.clone(), self.top_color), display)?];
+        if !side.is_empty() {
+            meshes.push(side.build(colored_material(format!("{} side", self.name), self.side_color), display)?);
+        }
+        Model::from_meshes(self.name.clone(), meshes, display, shader_cache)
+    }
+
+    // Returns the top (cap and bevel) and side geometry separately so they can use different materials
+    pub fn build_meshes(&self) -> (MeshBuilder, MeshBuilder) {
+        let bevel = self.bevel.clamp(0.0, self.height.clamp(0.0, HEX_APOTHEM * 0.5));
+        let outer_radius = HEX_APOTHEM / (PI / 6.0).cos();
+        let inner_radius = outer_radius * (HEX_APOTHEM - bevel) / HEX_APOTHEM;
+        let corner = |radius: f32, index: usize, y: f32| {
+            let angle = PI / 6.0 + PI / 3.0 * index as f32;
+            Vector3::new(radius * angle.cos(), y, radius * angle.sin())
+        };
+        let top_uv = |position: Vector3| (position.x / (2.0 * outer_radius) + 0.5, position.z / (2.0 * outer_radius) + 0.5);
+
+        let mut top = MeshBuilder::new();
+        let center_position = Vector3::new(0.0, self.height, 0.0);
+        let center = top.add_vertex(center_position, Vector3::up(), Vector3::new(1.0, 0.0, 0.0), top_uv(center_position));
+        let inner: Vec<u32> = (0..6).map(|i| {
+            let position = corner(inner_radius, i, self.height);
+            top.add_vertex(position, Vector3::up(), Vector3::new(1.0, 0.0, 0.0), top_uv(position))
+        }).collect();
+        for i in 0..6 {
+            top.add_triangle(center, inner[(i + 1) % 6], inner[i]);
+        }
+
+        let side_top = self.height - bevel;
+        if bevel > 0.0 {
+            for i in 0..6 {
+                let next = (i + 1) % 6;
+                let edge_normal = edge_normal(i);
+                let normal = Vector3::new(edge_normal.x, 1.0, edge_normal.z).normalize_or(Vector3::up());
+                let tangent = (corner(1.0, next, 0.0) - corner(1.0, i, 0.0)).normalize_or(Vector3::right());
+                let quad: Vec<u32> = [
+                    corner(outer_radius, i, side_top),
+                    corner(inner_radius, i, self.height),
+                    corner(inner_radius, next, self.height),
+                    corner(outer_radius, next, side_top),
+                ].into_iter().map(|position| top.add_vertex(position, normal, tangent, top_uv(position))).collect();
+                top.add_quad(quad[0], quad[1], quad[2], quad[3]);
+            }
+        }
+
+        // No bottom cap, tiles always stand on the ground or on another tile
+        let mut side = MeshBuilder::new();
+        if side_top > 0.0 {
+            for i in 0..6 {
+                let next = (i + 1) % 6;
+                let normal = edge_normal(i);
+                let tangent = (corner(1.0, next, 0.0) - corner(1.0, i, 0.0)).normalize_or(Vector3::right());
+                let (u0, u1) = (i as f32 / 6.0, (i + 1) as f32 / 6.0);
+                let bottom_start = side.add_vertex(corner(outer_radius, i, 0.0), normal, tangent, (u0, 0.0));
+                let top_start = side.add_vertex(corner(outer_radius, i, side_top), normal, tangent, (u0, 1.0));
+                let top_end = side.add_vertex(corner(outer_radius, next, side_top), normal, tangent, (u1, 1.0));
+                let bottom_end = side.add_vertex(corner(outer_radius, next, 0.0), normal, tangent, (u1, 0.0));
+                side.add_quad(bottom_start, top_start, top_end, bottom_end);
+            }
+        }
+        (top, side)
+    }
+}
+
+// Continuous surface through one height sample per tile, samples are laid out in columns (x) of rows (z)
+// where odd columns are shifted half a tile, the same layout the generator places tiles in.
+// Every sample gets its own colour, blended smoothly across the surface through a small texture
+pub struct HeightmapTerrain {
+    pub name: String,
+    pub width: usize,
+    pub depth: usize,
+    // World position of each sample, index x + z * width
+    pub positions: Vec<Vector3>,
+    pub colors: Vec<Vector3>,
+}
+
+impl HeightmapTerrain {
+    pub fn new(name: String, width: usize, depth: usize) -> HeightmapTerrain {
+        HeightmapTerrain {
+            name,
+            width,
+            depth,
+            positions: vec![Vector3::zero(); width * depth],
+            colors: vec![Vector3::one(); width * depth],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, z: usize, position: Vector3, color: Vector3) {
+        self.positions[x + z * self.width] = position;
+        self.colors[x + z * self.width] = color;
+    }
+
+    pub fn build(&self, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<Model> {
+        let color_map = self.color_map(display)?;
+        let mut material = colored_material(self.name.clone(), Vector3::one());
+        material.diffuse_texture = Some(color_map);
+        let mesh = self.build_mesh().build(material, display)?;
+        Model::from_meshes(self.name.clone(), vec![mesh], display, shader_cache)
+    }
+
+    pub fn build_mesh(&self) -> MeshBuilder {
+        let mut builder = MeshBuilder::new();
+        for z in 0..self.depth {
+            for x in 0..self.width {
+                // Texel centres so every sample shows exactly its own colour
+                let tex_coords = ((x as f32 + 0.5) / self.width as f32, (z as f32 + 0.5) / self.depth as f32);
+                builder.add_vertex(self.positions[x + z * self.width], Vector3::up(), Vector3::new(1.0, 0.0, 0.0), tex_coords);
+            }
+        }
+
+        // Two triangles per sample, towards the neighbours at 0, 60 and 120 degrees
+        let index = |x: usize, z: i64| -> Option<u32> {
+            if x >= self.width || z < 0 || z >= self.depth as i64 {
+                return None;
+            }
+            Some((x + z as usize * self.width) as u32)
+        };
+        for z in 0..self.depth as i64 {
+            for x in 0..self.width {
+                let odd = x % 2 == 1;
+                let center = index(x, z);
+                let ahead = index(x, z + 1);
+                let next_up = index(x + 1, if odd { z + 1 } else { z });
+                let next_down = index(x + 1, if odd { z } else { z - 1 });
+                if let (Some(center), Some(ahead), Some(next_up)) = (center, ahead, next_up) {
+                    builder.add_triangle(center, next_up, ahead);
+                }
+                if let (Some(center), Some(next_up), Some(next_down)) = (center, next_up, next_down) {
+                    builder.add_triangle(center, next_down, next_up);
+                }
+            }
+        }
+        builder.smooth_normals();
+        builder
+    }
+
+    fn color_map(&self, display: &Display<WindowSurface>) -> Result<Rc<Texture2d>> {
+        let pixels: Vec<u8> = self.colors.iter()
+            .flat_map(|color| [color.x, color.y, color.z, 1.0])
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+        let image = RawImage2d::from_raw_rgba(pixels, (self.width as u32, self.depth as u32));
+        let texture = Texture2d::new(display, image).map_err(|e| Error::Texture {
+            path: format!("<{} color map>", self.name).into(),
+            message: e.to_string(),
+        })?;
+        Ok(Rc::new(texture))
+    }
+}
+
+fn colored_material(name: String, color: Vector3) -> Material {
+    let mut material = Material::new(name, Shader::new(DIFFUSE_VERTEX_SHADER, DIFFUSE_FRAGMENT_SHADER), color * 0.1, color, Vector3::new(0.1, 0.1, 0.1));
+    material.shininess = 8.0;
+    material
+}
+
+// Outward normal of the side between corners i and i + 1, corners sit at 30 + i * 60 degrees
+fn edge_normal(index: usize) -> Vector3 {
+    let angle = PI / 3.0 * (index + 1) as f32;
+    Vector3::new(angle.cos(), 0.0, angle.sin())
+}
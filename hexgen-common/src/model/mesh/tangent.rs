@@ -0,0 +1,16 @@
+use glium::implement_vertex;
+
+#[derive(Copy, Clone)]
+pub struct Tangent {
+    tangent: (f32, f32, f32),
+}
+
+implement_vertex!(Tangent, tangent);
+
+impl Tangent {
+    pub fn new(tangent: (f32, f32, f32)) -> Tangent {
+        Tangent {
+            tangent
+        }
+    }
+}
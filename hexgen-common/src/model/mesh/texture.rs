@@ -1,13 +1,85 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use glium::glutin::surface::WindowSurface;
+use glium::texture::RawImage2d;
+use glium::{Display, Texture2d};
+use tracing::info;
+use crate::error::{Error, Result};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TextureKind {
+    Diffuse,
+    Normal,
+}
+
 pub struct Texture {
-    id: usize,
-    tex_type: String,
+    pub path: String,
+    pub kind: TextureKind,
+    pub texture: Rc<Texture2d>,
 }
 
 impl Texture {
-    pub fn new(id: usize, tex_type: String) -> Texture {
+    pub fn new(path: String, kind: TextureKind, texture: Rc<Texture2d>) -> Texture {
         Texture {
-            id,
-            tex_type,
+            path,
+            kind,
+            texture,
+        }
+    }
+}
+
+// Keeps every texture loaded once per path so models sharing a texture atlas share the GPU copy
+pub struct TextureCache {
+    textures: HashMap<String, Rc<Texture2d>>,
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        TextureCache::new()
+    }
+}
+
+impl TextureCache {
+    pub fn new() -> TextureCache {
+        TextureCache {
+            textures: HashMap::new(),
         }
     }
-}
\ No newline at end of file
+
+    pub fn load(&mut self, path: &str, display: &Display<WindowSurface>) -> Result<Rc<Texture2d>> {
+        if let Some(texture) = self.textures.get(path) {
+            return Ok(texture.clone());
+        }
+
+        let image = image::open(path).map_err(|e| Error::Texture {
+            path: PathBuf::from(path),
+            message: e.to_string(),
+        })?.to_rgba8();
+        let dimensions = image.dimensions();
+        let raw_image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+        let texture = Rc::new(Texture2d::new(display, raw_image).map_err(|e| Error::Texture {
+            path: PathBuf::from(path),
+            message: e.to_string(),
+        })?);
+
+        info!("Loaded texture at path {}", path);
+        self.textures.insert(path.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    // Releases textures no material references anymore, returns how many were dropped
+    pub fn unload_unused(&mut self) -> usize {
+        let before = self.textures.len();
+        self.textures.retain(|_, texture| Rc::strong_count(texture) > 1);
+        before - self.textures.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+}
@@ -15,4 +15,8 @@ impl Vertex {
             tex_coords,
         }
     }
+
+    pub fn position(&self) -> (f32, f32, f32) {
+        self.position
+    }
 }
\ No newline at end of file
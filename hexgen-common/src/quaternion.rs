@@ -0,0 +1,253 @@
+use std::ops;
+use crate::matrix::Matrix;
+use crate::vector3::Vector3;
+
+// Unit quaternion rotation. Unlike Matrix, a * b applies b first and then a
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Quaternion::identity()
+    }
+}
+
+impl ops::Mul<Quaternion> for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Self::Output {
+        Quaternion::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+impl ops::MulAssign<Quaternion> for Quaternion {
+    fn mul_assign(&mut self, rhs: Quaternion) {
+        *self = *self * rhs;
+    }
+}
+
+impl ops::Mul<Vector3> for Quaternion {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        self.rotate(rhs)
+    }
+}
+
+impl Quaternion {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quaternion {
+        Quaternion {
+            x,
+            y,
+            z,
+            w,
+        }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    // Same sense as the rotation matrices, a positive angle around up turns right into back
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Quaternion {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion::new(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    // Angles in radians around X, then Y, then Z, the order GameObject used for its Euler rotation
+    pub fn from_euler(angles: Vector3) -> Quaternion {
+        Quaternion::from_axis_angle(Vector3::forward(), angles.z)
+            * Quaternion::from_axis_angle(Vector3::up(), angles.y)
+            * Quaternion::from_axis_angle(Vector3::right(), angles.x)
+    }
+
+    // Turns forward() onto direction while keeping up() as close to up as possible
+    pub fn look_rotation(direction: Vector3, up: Vector3) -> Quaternion {
+        let forward = direction.normalize_or(Vector3::forward());
+        let mut right = up.cross(forward).normalize();
+        if right == Vector3::zero() {
+            // Looking straight along up, any right axis perpendicular to forward will do
+            right = Vector3::forward().cross(forward).normalize_or(Vector3::right());
+        }
+        let up = forward.cross(right);
+        Quaternion::from_basis(right, up, forward)
+    }
+
+    // The rotation taking right(), up() and forward() onto the given orthonormal axes
+    fn from_basis(right: Vector3, up: Vector3, forward: Vector3) -> Quaternion {
+        let trace = right.x + up.y + forward.z;
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new((up.z - forward.y) / s, (forward.x - right.z) / s, (right.y - up.x) / s, s / 4.0)
+        } else if right.x > up.y && right.x > forward.z {
+            let s = (1.0 + right.x - up.y - forward.z).sqrt() * 2.0;
+            Quaternion::new(s / 4.0, (up.x + right.y) / s, (forward.x + right.z) / s, (up.z - forward.y) / s)
+        } else if up.y > forward.z {
+            let s = (1.0 + up.y - right.x - forward.z).sqrt() * 2.0;
+            Quaternion::new((up.x + right.y) / s, s / 4.0, (forward.y + up.z) / s, (forward.x - right.z) / s)
+        } else {
+            let s = (1.0 + forward.z - right.x - up.y).sqrt() * 2.0;
+            Quaternion::new((forward.x + right.z) / s, (forward.y + up.z) / s, s / 4.0, (right.y - up.x) / s)
+        };
+        quaternion.normalize()
+    }
+
+    pub fn dot(&self, rhs: Quaternion) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            Quaternion::identity()
+        } else {
+            Quaternion::new(self.x / length, self.y / length, self.z / length, self.w / length)
+        }
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    // The conjugate for unit quaternions, which is all this type is meant to hold
+    pub fn inverse(&self) -> Quaternion {
+        let length_squared = self.dot(*self);
+        if length_squared <= f32::EPSILON {
+            return Quaternion::identity();
+        }
+        let conjugate = self.conjugate();
+        Quaternion::new(conjugate.x / length_squared, conjugate.y / length_squared, conjugate.z / length_squared, conjugate.w / length_squared)
+    }
+
+    pub fn rotate(&self, vector: Vector3) -> Vector3 {
+        let axis = Vector3::new(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+        vector + t * self.w + axis.cross(t)
+    }
+
+    // Spherical interpolation along the shorter arc
+    pub fn slerp(&self, rhs: Quaternion, t: f32) -> Quaternion {
+        let mut cos = self.dot(rhs);
+        let mut rhs = rhs;
+        if cos < 0.0 {
+            cos = -cos;
+            rhs = Quaternion::new(-rhs.x, -rhs.y, -rhs.z, -rhs.w);
+        }
+
+        // Nearly parallel rotations divide by a vanishing sine, a normalized lerp is exact enough there
+        let (from, to) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion::new(
+            self.x * from + rhs.x * to,
+            self.y * from + rhs.y * to,
+            self.z * from + rhs.z * to,
+            self.w * from + rhs.w * to,
+        ).normalize()
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        let right = self.rotate(Vector3::right());
+        let up = self.rotate(Vector3::up());
+        let forward = self.rotate(Vector3::forward());
+        Matrix([
+            [right.x, right.y, right.z, 0.0],
+            [up.x, up.y, up.z, 0.0],
+            [forward.x, forward.y, forward.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Same angle within epsilon, q and -q are the same rotation
+    pub fn approx_eq(&self, rhs: Quaternion, epsilon: f32) -> bool {
+        1.0 - self.dot(rhs).abs() <= epsilon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    const EPSILON: f32 = 1e-5;
+
+    #[test]
+    fn axis_angle_rotates_like_matrices() {
+        let quarter_y = Quaternion::from_axis_angle(Vector3::up(), FRAC_PI_2);
+        assert!(quarter_y.rotate(Vector3::right()).approx_eq(Vector3::back(), EPSILON));
+        let quarter_x = Quaternion::from_axis_angle(Vector3::right(), FRAC_PI_2);
+        assert!(quarter_x.rotate(Vector3::up()).approx_eq(Vector3::forward(), EPSILON));
+        let quarter_z = Quaternion::from_axis_angle(Vector3::forward(), FRAC_PI_2);
+        assert!(quarter_z.rotate(Vector3::right()).approx_eq(Vector3::up(), EPSILON));
+
+        let point = Vector3::new(1.0, 2.0, 3.0);
+        assert!(quarter_y.to_matrix().transform_point(point).approx_eq(quarter_y.rotate(point), EPSILON));
+    }
+
+    #[test]
+    fn composition_and_inverse() {
+        let a = Quaternion::from_axis_angle(Vector3::up(), 0.7);
+        let b = Quaternion::from_axis_angle(Vector3::right(), -1.2);
+        let point = Vector3::new(0.5, -1.0, 2.0);
+        assert!((a * b).rotate(point).approx_eq(a.rotate(b.rotate(point)), EPSILON));
+        assert!((b.to_matrix() * a.to_matrix()).transform_point(point).approx_eq((a * b).rotate(point), EPSILON));
+        assert!((a * a.inverse()).approx_eq(Quaternion::identity(), EPSILON));
+        assert!(a.inverse().rotate(a.rotate(point)).approx_eq(point, EPSILON));
+    }
+
+    #[test]
+    fn euler_order() {
+        let angles = Vector3::new(0.3, -0.8, 1.1);
+        let expected = Quaternion::from_axis_angle(Vector3::forward(), angles.z)
+            * Quaternion::from_axis_angle(Vector3::up(), angles.y)
+            * Quaternion::from_axis_angle(Vector3::right(), angles.x);
+        assert!(Quaternion::from_euler(angles).approx_eq(expected, EPSILON));
+        assert!(Quaternion::from_euler(Vector3::zero()).approx_eq(Quaternion::identity(), EPSILON));
+    }
+
+    #[test]
+    fn look_rotation() {
+        let direction = Vector3::new(1.0, -1.0, 2.0);
+        let rotation = Quaternion::look_rotation(direction, Vector3::up());
+        assert!(rotation.rotate(Vector3::forward()).approx_eq(direction.normalize(), EPSILON));
+        assert!(rotation.rotate(Vector3::right()).y.abs() <= EPSILON);
+        assert!(rotation.rotate(Vector3::up()).y > 0.0);
+
+        let backwards = Quaternion::look_rotation(Vector3::back(), Vector3::up());
+        assert!(backwards.rotate(Vector3::forward()).approx_eq(Vector3::back(), EPSILON));
+        let down = Quaternion::look_rotation(Vector3::down(), Vector3::up());
+        assert!(down.rotate(Vector3::forward()).approx_eq(Vector3::down(), EPSILON));
+    }
+
+    #[test]
+    fn slerp() {
+        let from = Quaternion::identity();
+        let to = Quaternion::from_axis_angle(Vector3::up(), PI / 2.0);
+        assert!(from.slerp(to, 0.0).approx_eq(from, EPSILON));
+        assert!(from.slerp(to, 1.0).approx_eq(to, EPSILON));
+        assert!(from.slerp(to, 0.5).approx_eq(Quaternion::from_axis_angle(Vector3::up(), PI / 4.0), EPSILON));
+
+        // The negated target is the same rotation and must take the same path
+        let negated = Quaternion::new(-to.x, -to.y, -to.z, -to.w);
+        assert!(from.slerp(negated, 0.5).approx_eq(Quaternion::from_axis_angle(Vector3::up(), PI / 4.0), EPSILON));
+    }
+}
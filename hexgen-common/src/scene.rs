@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use crate::ecs::components::SceneNode;
+use crate::ecs::{Entity, World};
+use crate::game_object::GameObject;
+use crate::matrix::Matrix;
+use crate::vector3::Vector3;
+
+// Handle to a node, stays invalid once the node is removed even if its slot gets reused
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+struct Node {
+    game_object: GameObject,
+    entity: Entity,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world_matrix: Matrix,
+    // Local transform changed since the world matrix was last calculated
+    dirty: bool,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+// Game objects arranged in a hierarchy, a child's transform is relative to its parent.
+// World matrices are recalculated lazily in update_world_matrices, only for changed subtrees.
+// Every node owns an entity in world, components attached to it are dropped with the node
+pub struct Scene {
+    pub world: World,
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+    names: HashMap<String, Vec<NodeId>>,
+    dirty: bool,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Scene::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene {
+            world: World::new(),
+            slots: vec![],
+            free: vec![],
+            roots: vec![],
+            names: HashMap::new(),
+            dirty: false,
+        }
+    }
+
+    pub fn add(&mut self, game_object: GameObject) -> NodeId {
+        let id = self.insert(game_object, None);
+        self.roots.push(id);
+        id
+    }
+
+    // Falls back to adding a root when the parent no longer exists
+    pub fn add_child(&mut self, parent: NodeId, game_object: GameObject) -> NodeId {
+        if !self.contains(parent) {
+            return self.add(game_object);
+        }
+        let id = self.insert(game_object, Some(parent));
+        self.node_mut(parent).unwrap().children.push(id);
+        id
+    }
+
+    fn insert(&mut self, game_object: GameObject, parent: Option<NodeId>) -> NodeId {
+        let name = game_object.name().to_string();
+        let entity = self.world.spawn();
+        let node = Node {
+            world_matrix: game_object.model_matrix,
+            game_object,
+            entity,
+            parent,
+            children: vec![],
+            dirty: true,
+        };
+        self.dirty = true;
+
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.node = Some(node);
+                NodeId { index, generation: slot.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, node: Some(node) });
+                NodeId { index: self.slots.len() - 1, generation: 0 }
+            }
+        };
+        self.names.entry(name).or_default().push(id);
+        self.world.insert(entity, SceneNode(id));
+        id
+    }
+
+    // Removes the node together with its whole subtree, returns how many nodes were removed
+    pub fn remove(&mut self, id: NodeId) -> usize {
+        let parent = match self.node(id) {
+            Some(node) => node.parent,
+            None => return 0,
+        };
+        match parent.and_then(|parent| self.node_mut(parent)) {
+            Some(parent) => parent.children.retain(|child| *child != id),
+            None => self.roots.retain(|root| *root != id),
+        }
+
+        let subtree = self.descendants(id);
+        for removed in &subtree {
+            let slot = &mut self.slots[removed.index];
+            if let Some(node) = slot.node.take() {
+                self.world.despawn(node.entity);
+                if let Some(ids) = self.names.get_mut(node.game_object.name()) {
+                    ids.retain(|named| named != removed);
+                    if ids.is_empty() {
+                        self.names.remove(node.game_object.name());
+                    }
+                }
+            }
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(removed.index);
+        }
+        subtree.len()
+    }
+
+    // Slots are kept with their generation bumped, so ids from before the clear stay invalid
+    pub fn clear(&mut self) {
+        self.world.clear();
+        for slot in &mut self.slots {
+            if slot.node.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+        }
+        self.free = (0..self.slots.len()).rev().collect();
+        self.roots.clear();
+        self.names.clear();
+        self.dirty = false;
+    }
+
+    // Keeps the local transform, so the node moves with its new parent. Refuses to create cycles
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        if !self.contains(id) || parent.is_some_and(|parent| !self.contains(parent) || self.is_ancestor(id, parent)) {
+            return false;
+        }
+
+        match self.node(id).unwrap().parent.and_then(|old| self.node_mut(old)) {
+            Some(old_parent) => old_parent.children.retain(|child| *child != id),
+            None => self.roots.retain(|root| *root != id),
+        }
+        match parent {
+            Some(parent) => self.node_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        let node = self.node_mut(id).unwrap();
+        node.parent = parent;
+        node.dirty = true;
+        self.dirty = true;
+        true
+    }
+
+    // True when ancestor is id itself or anywhere above it
+    pub fn is_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut current = Some(id);
+        while let Some(node_id) = current {
+            if node_id == ancestor {
+                return true;
+            }
+            current = self.parent(node_id);
+        }
+        false
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.node(id).is_some()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&GameObject> {
+        self.node(id).map(|node| &node.game_object)
+    }
+
+    // Marks the node as changed, its world matrix and those of its children are recalculated on the next update
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut GameObject> {
+        let node = self.node_mut(id)?;
+        node.dirty = true;
+        self.dirty = true;
+        self.node_mut(id).map(|node| &mut node.game_object)
+    }
+
+    pub fn entity(&self, id: NodeId) -> Option<Entity> {
+        self.node(id).map(|node| node.entity)
+    }
+
+    pub fn node_of(&self, entity: Entity) -> Option<NodeId> {
+        self.world.get::<SceneNode>(entity).map(|node| node.0)
+    }
+
+    // First node with the given name, names don't have to be unique
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name).and_then(|ids| ids.first().copied())
+    }
+
+    pub fn find_all(&self, name: &str) -> &[NodeId] {
+        self.names.get(name).map_or(&[], |ids| ids.as_slice())
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).and_then(|node| node.parent)
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.node(id).map_or(&[], |node| node.children.as_slice())
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    // Valid after update_world_matrices, until the node or one of its ancestors changes
+    pub fn world_matrix(&self, id: NodeId) -> Option<&Matrix> {
+        self.node(id).map(|node| &node.world_matrix)
+    }
+
+    pub fn world_position(&self, id: NodeId) -> Option<Vector3> {
+        self.world_matrix(id).map(|matrix| Vector3::new(matrix.0[3][0], matrix.0[3][1], matrix.0[3][2]))
+    }
+
+    pub fn update_world_matrices(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        // Each entry carries its parent's world matrix and whether that changed during this update
+        let mut stack: Vec<(NodeId, Matrix, bool)> = self.roots.iter().rev().map(|root| (*root, Matrix::identity(), false)).collect();
+        while let Some((id, parent_world, parent_changed)) = stack.pop() {
+            let node = match self.node_mut(id) {
+                Some(node) => node,
+                None => continue,
+            };
+            let changed = node.dirty || parent_changed;
+            if changed {
+                node.world_matrix = node.game_object.model_matrix * parent_world;
+                node.dirty = false;
+            }
+            let world = node.world_matrix;
+            stack.extend(node.children.iter().rev().map(|child| (*child, world, changed)));
+        }
+        self.dirty = false;
+    }
+
+    // Depth-first, parents before their children, with the depth below the roots
+    pub fn traverse<F: FnMut(NodeId, &GameObject, usize)>(&self, mut visit: F) {
+        let mut stack: Vec<(NodeId, usize)> = self.roots.iter().rev().map(|root| (*root, 0)).collect();
+        while let Some((id, depth)) = stack.pop() {
+            if let Some(node) = self.node(id) {
+                visit(id, &node.game_object, depth);
+                stack.extend(node.children.iter().rev().map(|child| (*child, depth + 1)));
+            }
+        }
+    }
+
+    // The node itself followed by everything below it, depth-first
+    pub fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        let mut result = vec![];
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if let Some(node) = self.node(current) {
+                result.push(current);
+                stack.extend(node.children.iter().rev());
+            }
+        }
+        result
+    }
+
+    // Every node in storage order together with its world matrix
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &GameObject, &Matrix)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.node.as_ref().map(|node| (NodeId { index, generation: slot.generation }, &node.game_object, &node.world_matrix))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn node(&self, id: NodeId) -> Option<&Node> {
+        self.slots.get(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_ref())
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.slots.get_mut(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::Translation;
+
+    fn object(name: &str, x: f32) -> GameObject {
+        let mut game_object = GameObject::new(name.to_string());
+        game_object.translate(Vector3::new(x, 0.0, 0.0));
+        game_object
+    }
+
+    #[test]
+    fn remove_takes_subtree() {
+        let mut scene = Scene::new();
+        let root = scene.add(object("root", 0.0));
+        let child = scene.add_child(root, object("child", 1.0));
+        let grandchild = scene.add_child(child, object("grandchild", 1.0));
+        let sibling = scene.add_child(root, object("sibling", 1.0));
+        scene.world.insert(scene.entity(grandchild).unwrap(), 7u32);
+
+        assert_eq!(scene.remove(child), 2);
+        assert_eq!(scene.len(), 2);
+        assert!(!scene.contains(child) && !scene.contains(grandchild));
+        assert_eq!(scene.children(root), &[sibling]);
+        assert_eq!(scene.find("grandchild"), None);
+        assert_eq!(scene.world.count::<u32>(), 0);
+        // Removing again does nothing, a reused slot doesn't revive the old id
+        assert_eq!(scene.remove(child), 0);
+        let reused = scene.add(object("reused", 0.0));
+        assert!(scene.contains(reused));
+        assert!(!scene.contains(child) && !scene.contains(grandchild));
+    }
+
+    #[test]
+    fn set_parent_refuses_cycles() {
+        let mut scene = Scene::new();
+        let a = scene.add(object("a", 0.0));
+        let b = scene.add_child(a, object("b", 0.0));
+        let c = scene.add_child(b, object("c", 0.0));
+
+        assert!(!scene.set_parent(a, Some(c)));
+        assert!(!scene.set_parent(a, Some(a)));
+        assert_eq!(scene.parent(a), None);
+        assert_eq!(scene.roots(), &[a]);
+
+        assert!(scene.set_parent(c, Some(a)));
+        assert_eq!(scene.children(a), &[b, c]);
+        assert!(scene.children(b).is_empty());
+        assert!(scene.set_parent(b, None));
+        assert_eq!(scene.roots(), &[a, b]);
+    }
+
+    #[test]
+    fn world_matrices_follow_changed_parents() {
+        let mut scene = Scene::new();
+        let root = scene.add(object("root", 1.0));
+        let child = scene.add_child(root, object("child", 2.0));
+        let grandchild = scene.add_child(child, object("grandchild", 3.0));
+        let other = scene.add(object("other", 5.0));
+        scene.update_world_matrices();
+        assert_eq!(scene.world_position(grandchild), Some(Vector3::new(6.0, 0.0, 0.0)));
+
+        // Only the changed node is marked, its descendants pick up the change through the update
+        scene.get_mut(child).unwrap().translate(Vector3::new(10.0, 0.0, 0.0));
+        assert_eq!(scene.world_position(grandchild), Some(Vector3::new(6.0, 0.0, 0.0)));
+        scene.update_world_matrices();
+        assert_eq!(scene.world_position(child), Some(Vector3::new(13.0, 0.0, 0.0)));
+        assert_eq!(scene.world_position(grandchild), Some(Vector3::new(16.0, 0.0, 0.0)));
+        assert_eq!(scene.world_position(root), Some(Vector3::new(1.0, 0.0, 0.0)));
+        assert_eq!(scene.world_position(other), Some(Vector3::new(5.0, 0.0, 0.0)));
+
+        assert!(scene.set_parent(grandchild, Some(other)));
+        scene.update_world_matrices();
+        assert_eq!(scene.world_position(grandchild), Some(Vector3::new(8.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn clear_invalidates_ids() {
+        let mut scene = Scene::new();
+        let old = scene.add(object("old", 0.0));
+        scene.clear();
+        assert!(scene.is_empty());
+
+        let new = scene.add(object("new", 0.0));
+        assert_ne!(old, new);
+        assert!(!scene.contains(old));
+        assert_eq!(scene.get(old).map(|game_object| game_object.name()), None);
+        assert_eq!(scene.len(), 1);
+    }
+}
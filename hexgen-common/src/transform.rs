@@ -1,13 +1,192 @@
+use crate::matrix::Matrix;
+use crate::quaternion::Quaternion;
 use crate::vector3::Vector3;
 
+// The traits are incremental, translating twice moves twice as far
 pub trait Translation {
     fn translate(&mut self, translation: Vector3);
 }
 
 pub trait Rotation {
-    fn rotate(&mut self, rotation: Vector3);
+    fn rotate(&mut self, rotation: Quaternion);
 }
 
 pub trait Scale {
     fn scale(&mut self, scale: Vector3);
 }
+
+// Position, rotation and scale, applied as scale first, then rotation, then translation
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Transform {
+    pub position: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::new()
+    }
+}
+
+impl Transform {
+    pub fn new() -> Transform {
+        Transform {
+            position: Vector3::zero(),
+            rotation: Quaternion::identity(),
+            scale: Vector3::one(),
+        }
+    }
+
+    pub fn from_position(position: Vector3) -> Transform {
+        Transform {
+            position,
+            ..Transform::new()
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: Quaternion) -> Transform {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3) -> Transform {
+        self.scale = scale;
+        self
+    }
+
+    pub fn matrix(&self) -> Matrix {
+        let right = self.rotation.rotate(Vector3::right()) * self.scale.x;
+        let up = self.rotation.rotate(Vector3::up()) * self.scale.y;
+        let forward = self.rotation.rotate(Vector3::forward()) * self.scale.z;
+        Matrix([
+            [right.x, right.y, right.z, 0.0],
+            [up.x, up.y, up.z, 0.0],
+            [forward.x, forward.y, forward.z, 0.0],
+            [self.position.x, self.position.y, self.position.z, 1.0],
+        ])
+    }
+
+    pub fn right(&self) -> Vector3 {
+        self.rotation.rotate(Vector3::right())
+    }
+
+    pub fn up(&self) -> Vector3 {
+        self.rotation.rotate(Vector3::up())
+    }
+
+    pub fn forward(&self) -> Vector3 {
+        self.rotation.rotate(Vector3::forward())
+    }
+
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        self.rotation.rotate(point * self.scale) + self.position
+    }
+
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        self.rotation.rotate(vector * self.scale)
+    }
+
+    // Turns forward towards target
+    pub fn look_at(&mut self, target: Vector3, up: Vector3) {
+        self.rotation = Quaternion::look_rotation(target - self.position, up);
+    }
+
+    // Rotates around the local axes instead of the world ones
+    pub fn rotate_local(&mut self, rotation: Quaternion) {
+        self.rotation = (self.rotation * rotation).normalize();
+    }
+
+    // Moves along the local axes, scale doesn't affect the distance
+    pub fn translate_local(&mut self, translation: Vector3) {
+        self.position += self.rotation.rotate(translation);
+    }
+
+    // Position and scale are interpolated linearly, rotation along the shorter arc
+    pub fn lerp(&self, rhs: &Transform, t: f32) -> Transform {
+        Transform {
+            position: self.position.lerp(rhs.position, t),
+            rotation: self.rotation.slerp(rhs.rotation, t),
+            scale: self.scale.lerp(rhs.scale, t),
+        }
+    }
+}
+
+impl Translation for Transform {
+    fn translate(&mut self, translation: Vector3) {
+        self.position += translation;
+    }
+}
+
+// World space, applied after the current rotation
+impl Rotation for Transform {
+    fn rotate(&mut self, rotation: Quaternion) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+}
+
+impl Scale for Transform {
+    fn scale(&mut self, scale: Vector3) {
+        self.scale = self.scale * scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    const EPSILON: f32 = 1e-5;
+
+    #[test]
+    fn matrix_applies_scale_rotation_translation() {
+        let transform = Transform::from_position(Vector3::new(1.0, 2.0, 3.0))
+            .with_rotation(Quaternion::from_axis_angle(Vector3::up(), FRAC_PI_2))
+            .with_scale(Vector3::new(2.0, 1.0, 1.0));
+        let expected = Matrix::scaling(transform.scale) * transform.rotation.to_matrix() * Matrix::translation(transform.position);
+        assert!(transform.matrix().approx_eq(&expected, EPSILON));
+
+        let point = Vector3::new(1.0, 0.0, 0.0);
+        assert!(transform.transform_point(point).approx_eq(Vector3::new(1.0, 2.0, 1.0), EPSILON));
+        assert!(transform.matrix().transform_point(point).approx_eq(transform.transform_point(point), EPSILON));
+    }
+
+    #[test]
+    fn scaling_keeps_position() {
+        let mut transform = Transform::new();
+        transform.scale(Vector3::new(2.0, 2.0, 2.0));
+        transform.translate(Vector3::new(1.0, 0.0, 0.0));
+        transform.scale(Vector3::new(3.0, 1.0, 1.0));
+        transform.translate(Vector3::new(1.0, 1.0, 0.0));
+        assert_eq!(transform.position, Vector3::new(2.0, 1.0, 0.0));
+        assert_eq!(transform.scale, Vector3::new(6.0, 2.0, 2.0));
+        assert_eq!(transform.matrix().transform_point(Vector3::zero()), transform.position);
+    }
+
+    #[test]
+    fn incremental_rotation() {
+        let quarter = Quaternion::from_axis_angle(Vector3::up(), FRAC_PI_2);
+        let mut transform = Transform::new();
+        transform.rotate(quarter);
+        transform.rotate(quarter);
+        assert!(transform.forward().approx_eq(Vector3::back(), EPSILON));
+
+        let mut local = Transform::new().with_rotation(Quaternion::from_axis_angle(Vector3::right(), FRAC_PI_2));
+        local.rotate_local(quarter);
+        assert!(local.up().approx_eq(Vector3::forward(), EPSILON));
+        local.translate_local(Vector3::up());
+        assert!(local.position.approx_eq(Vector3::forward(), EPSILON));
+    }
+
+    #[test]
+    fn look_at_and_lerp() {
+        let mut transform = Transform::from_position(Vector3::new(0.0, 1.0, 0.0));
+        transform.look_at(Vector3::new(3.0, 1.0, 0.0), Vector3::up());
+        assert!(transform.forward().approx_eq(Vector3::right(), EPSILON));
+
+        let halfway = Transform::new().lerp(&transform, 0.5);
+        assert!(halfway.position.approx_eq(Vector3::new(0.0, 0.5, 0.0), EPSILON));
+        let diagonal = Vector3::new(1.0, 0.0, 1.0).normalize();
+        assert!(halfway.forward().approx_eq(diagonal, EPSILON));
+    }
+}
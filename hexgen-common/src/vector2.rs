@@ -0,0 +1,200 @@
+use std::ops;
+
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct Vector2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl From<[f32; 2]> for Vector2 {
+    fn from(value: [f32; 2]) -> Self {
+        Vector2::new(value[0], value[1])
+    }
+}
+
+impl From<Vector2> for [f32; 2] {
+    fn from(value: Vector2) -> Self {
+        [value.x, value.y]
+    }
+}
+
+impl ops::Add<Vector2> for Vector2 {
+    type Output = Vector2;
+
+    fn add(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl ops::AddAssign<Vector2> for Vector2 {
+    fn add_assign(&mut self, rhs: Vector2) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl ops::Sub<Vector2> for Vector2 {
+    type Output = Vector2;
+
+    fn sub(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl ops::SubAssign<Vector2> for Vector2 {
+    fn sub_assign(&mut self, rhs: Vector2) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl ops::Mul<f32> for Vector2 {
+    type Output = Vector2;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vector2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl ops::MulAssign<f32> for Vector2 {
+    fn mul_assign(&mut self, rhs: f32) {
+        self.x *= rhs;
+        self.y *= rhs;
+    }
+}
+
+// Component-wise
+impl ops::Mul<Vector2> for Vector2 {
+    type Output = Vector2;
+
+    fn mul(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x * rhs.x, self.y * rhs.y)
+    }
+}
+
+impl ops::Div<f32> for Vector2 {
+    type Output = Vector2;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Vector2::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl ops::DivAssign<f32> for Vector2 {
+    fn div_assign(&mut self, rhs: f32) {
+        self.x /= rhs;
+        self.y /= rhs;
+    }
+}
+
+// Component-wise
+impl ops::Div<Vector2> for Vector2 {
+    type Output = Vector2;
+
+    fn div(self, rhs: Vector2) -> Self::Output {
+        Vector2::new(self.x / rhs.x, self.y / rhs.y)
+    }
+}
+
+impl ops::Neg for Vector2 {
+    type Output = Vector2;
+
+    fn neg(self) -> Self::Output {
+        Vector2::new(-self.x, -self.y)
+    }
+}
+
+impl Vector2 {
+    pub fn new(x: f32, y: f32) -> Vector2 {
+        Vector2 {
+            x,
+            y,
+        }
+    }
+
+    pub fn zero() -> Vector2 {
+        Vector2::new(0.0, 0.0)
+    }
+
+    pub fn one() -> Vector2 {
+        Vector2::new(1.0, 1.0)
+    }
+
+    pub fn dot(&self, rhs: Vector2) -> f32 {
+        self.x * rhs.x + self.y * rhs.y
+    }
+
+    // Z component of the 3D cross product, positive when rhs is counter-clockwise from self
+    pub fn perp_dot(&self, rhs: Vector2) -> f32 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    pub fn length_squared(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(&self, rhs: Vector2) -> f32 {
+        (*self - rhs).length()
+    }
+
+    // A zero vector has no direction and stays zero
+    pub fn normalize(&self) -> Vector2 {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            Vector2::zero()
+        } else {
+            *self / length
+        }
+    }
+
+    pub fn lerp(&self, rhs: Vector2, t: f32) -> Vector2 {
+        *self + (rhs - *self) * t
+    }
+
+    pub fn min(&self, rhs: Vector2) -> Vector2 {
+        Vector2::new(self.x.min(rhs.x), self.y.min(rhs.y))
+    }
+
+    pub fn max(&self, rhs: Vector2) -> Vector2 {
+        Vector2::new(self.x.max(rhs.x), self.y.max(rhs.y))
+    }
+
+    pub fn approx_eq(&self, rhs: Vector2, epsilon: f32) -> bool {
+        (self.x - rhs.x).abs() <= epsilon && (self.y - rhs.y).abs() <= epsilon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn products_and_length() {
+        let a = Vector2::new(3.0, 4.0);
+        let b = Vector2::new(-2.0, 1.0);
+        assert_eq!(a.dot(b), -2.0);
+        assert_eq!(a.perp_dot(b), 11.0);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.normalize(), Vector2::new(0.6, 0.8));
+        assert_eq!(Vector2::zero().normalize(), Vector2::zero());
+        assert_eq!(a.distance(Vector2::zero()), 5.0);
+    }
+
+    #[test]
+    fn component_ops() {
+        let a = Vector2::new(1.0, -2.0);
+        let b = Vector2::new(4.0, 2.0);
+        assert_eq!(a + b, Vector2::new(5.0, 0.0));
+        assert_eq!(a - b, Vector2::new(-3.0, -4.0));
+        assert_eq!(a * b, Vector2::new(4.0, -4.0));
+        assert_eq!(a / b, Vector2::new(0.25, -1.0));
+        assert_eq!(-a, Vector2::new(-1.0, 2.0));
+        assert_eq!(a.lerp(b, 0.5), Vector2::new(2.5, 0.0));
+        assert_eq!(a.min(b), Vector2::new(1.0, -2.0));
+        assert_eq!(a.max(b), Vector2::new(4.0, 2.0));
+    }
+}
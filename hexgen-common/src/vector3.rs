@@ -1,6 +1,6 @@
 use std::ops;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
 pub struct Vector3 {
     pub x: f32,
     pub y: f32,
@@ -67,6 +67,47 @@ impl ops::MulAssign<f32> for Vector3 {
     }
 }
 
+// Component-wise
+impl ops::Mul<Vector3> for Vector3 {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        Vector3::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z)
+    }
+}
+
+impl ops::Div<f32> for Vector3 {
+    type Output = Vector3;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Vector3::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+impl ops::DivAssign<f32> for Vector3 {
+    fn div_assign(&mut self, rhs: f32) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+    }
+}
+
+// Component-wise
+impl ops::Div<Vector3> for Vector3 {
+    type Output = Vector3;
+
+    fn div(self, rhs: Vector3) -> Self::Output {
+        Vector3::new(self.x / rhs.x, self.y / rhs.y, self.z / rhs.z)
+    }
+}
+
+impl ops::Neg for Vector3 {
+    type Output = Vector3;
+
+    fn neg(self) -> Self::Output {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
 
 impl Vector3 {
     pub fn new(x: f32, y: f32, z: f32) -> Vector3 {
@@ -140,4 +181,119 @@ impl Vector3 {
             z: -1f32,
         }
     }
+
+    pub fn dot(&self, rhs: Vector3) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    // Left-handed like the rest of the renderer, right().cross(up()) is forward()
+    pub fn cross(&self, rhs: Vector3) -> Vector3 {
+        Vector3::new(
+            self.y * rhs.z - self.z * rhs.y,
+            self.z * rhs.x - self.x * rhs.z,
+            self.x * rhs.y - self.y * rhs.x,
+        )
+    }
+
+    pub fn length_squared(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(&self, rhs: Vector3) -> f32 {
+        (*self - rhs).length()
+    }
+
+    // A zero vector has no direction and stays zero
+    pub fn normalize(&self) -> Vector3 {
+        self.normalize_or(Vector3::zero())
+    }
+
+    pub fn normalize_or(&self, fallback: Vector3) -> Vector3 {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            fallback
+        } else {
+            *self / length
+        }
+    }
+
+    pub fn lerp(&self, rhs: Vector3, t: f32) -> Vector3 {
+        *self + (rhs - *self) * t
+    }
+
+    pub fn min(&self, rhs: Vector3) -> Vector3 {
+        Vector3::new(self.x.min(rhs.x), self.y.min(rhs.y), self.z.min(rhs.z))
+    }
+
+    pub fn max(&self, rhs: Vector3) -> Vector3 {
+        Vector3::new(self.x.max(rhs.x), self.y.max(rhs.y), self.z.max(rhs.z))
+    }
+
+    pub fn abs(&self) -> Vector3 {
+        Vector3::new(self.x.abs(), self.y.abs(), self.z.abs())
+    }
+
+    pub fn approx_eq(&self, rhs: Vector3, epsilon: f32) -> bool {
+        (self.x - rhs.x).abs() <= epsilon && (self.y - rhs.y).abs() <= epsilon && (self.z - rhs.z).abs() <= epsilon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-6;
+
+    #[test]
+    fn dot_and_cross() {
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        let b = Vector3::new(4.0, -5.0, 6.0);
+        assert_eq!(a.dot(b), 12.0);
+        assert_eq!(a.cross(b), Vector3::new(27.0, 6.0, -13.0));
+        assert_eq!(a.cross(b).dot(a), 0.0);
+        assert_eq!(a.cross(b).dot(b), 0.0);
+        assert_eq!(Vector3::right().cross(Vector3::up()), Vector3::forward());
+    }
+
+    #[test]
+    fn length_and_normalize() {
+        let v = Vector3::new(2.0, -3.0, 6.0);
+        assert_eq!(v.length_squared(), 49.0);
+        assert_eq!(v.length(), 7.0);
+        assert!(v.normalize().approx_eq(Vector3::new(2.0 / 7.0, -3.0 / 7.0, 6.0 / 7.0), EPSILON));
+        assert!((v.normalize().length() - 1.0).abs() <= EPSILON);
+        assert_eq!(Vector3::zero().normalize(), Vector3::zero());
+        assert_eq!(Vector3::zero().normalize_or(Vector3::up()), Vector3::up());
+        assert_eq!(Vector3::zero().distance(v), 7.0);
+    }
+
+    #[test]
+    fn lerp() {
+        let a = Vector3::new(0.0, 10.0, -2.0);
+        let b = Vector3::new(4.0, 20.0, 2.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.25), Vector3::new(1.0, 12.5, -1.0));
+    }
+
+    #[test]
+    fn component_ops() {
+        let a = Vector3::new(1.0, -2.0, 3.0);
+        let b = Vector3::new(2.0, 4.0, -1.0);
+        assert_eq!(a * b, Vector3::new(2.0, -8.0, -3.0));
+        assert_eq!(a / b, Vector3::new(0.5, -0.5, -3.0));
+        assert_eq!(a / 2.0, Vector3::new(0.5, -1.0, 1.5));
+        assert_eq!(-a, Vector3::new(-1.0, 2.0, -3.0));
+        assert_eq!(a.min(b), Vector3::new(1.0, -2.0, -1.0));
+        assert_eq!(a.max(b), Vector3::new(2.0, 4.0, 3.0));
+        assert_eq!(a.abs(), Vector3::new(1.0, 2.0, 3.0));
+
+        let mut c = a;
+        c /= 2.0;
+        assert_eq!(c, a / 2.0);
+    }
 }
\ No newline at end of file
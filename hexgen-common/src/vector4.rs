@@ -0,0 +1,160 @@
+use std::ops;
+use crate::vector3::Vector3;
+
+// Homogeneous coordinates, w is 1 for points and 0 for directions
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct Vector4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl From<[f32; 4]> for Vector4 {
+    fn from(value: [f32; 4]) -> Self {
+        Vector4::new(value[0], value[1], value[2], value[3])
+    }
+}
+
+impl From<Vector4> for [f32; 4] {
+    fn from(value: Vector4) -> Self {
+        [value.x, value.y, value.z, value.w]
+    }
+}
+
+impl ops::Add<Vector4> for Vector4 {
+    type Output = Vector4;
+
+    fn add(self, rhs: Vector4) -> Self::Output {
+        Vector4::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z, self.w + rhs.w)
+    }
+}
+
+impl ops::Sub<Vector4> for Vector4 {
+    type Output = Vector4;
+
+    fn sub(self, rhs: Vector4) -> Self::Output {
+        Vector4::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z, self.w - rhs.w)
+    }
+}
+
+impl ops::Mul<f32> for Vector4 {
+    type Output = Vector4;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vector4::new(self.x * rhs, self.y * rhs, self.z * rhs, self.w * rhs)
+    }
+}
+
+// Component-wise
+impl ops::Mul<Vector4> for Vector4 {
+    type Output = Vector4;
+
+    fn mul(self, rhs: Vector4) -> Self::Output {
+        Vector4::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z, self.w * rhs.w)
+    }
+}
+
+impl ops::Div<f32> for Vector4 {
+    type Output = Vector4;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Vector4::new(self.x / rhs, self.y / rhs, self.z / rhs, self.w / rhs)
+    }
+}
+
+impl ops::Neg for Vector4 {
+    type Output = Vector4;
+
+    fn neg(self) -> Self::Output {
+        Vector4::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+impl Vector4 {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Vector4 {
+        Vector4 {
+            x,
+            y,
+            z,
+            w,
+        }
+    }
+
+    pub fn point(position: Vector3) -> Vector4 {
+        Vector4::new(position.x, position.y, position.z, 1.0)
+    }
+
+    pub fn direction(direction: Vector3) -> Vector4 {
+        Vector4::new(direction.x, direction.y, direction.z, 0.0)
+    }
+
+    pub fn xyz(&self) -> Vector3 {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    // Divides by w, used after a projection. Directions are returned unchanged
+    pub fn perspective_divide(&self) -> Vector3 {
+        if self.w == 0.0 {
+            self.xyz()
+        } else {
+            self.xyz() / self.w
+        }
+    }
+
+    pub fn dot(&self, rhs: Vector4) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Vector4 {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            Vector4::default()
+        } else {
+            *self / length
+        }
+    }
+
+    pub fn lerp(&self, rhs: Vector4, t: f32) -> Vector4 {
+        *self + (rhs - *self) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn products_and_length() {
+        let a = Vector4::new(1.0, 2.0, 2.0, 4.0);
+        let b = Vector4::new(2.0, -1.0, 0.5, 1.0);
+        assert_eq!(a.dot(b), 5.0);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.normalize(), Vector4::new(0.2, 0.4, 0.4, 0.8));
+        assert_eq!(Vector4::default().normalize(), Vector4::default());
+    }
+
+    #[test]
+    fn homogeneous() {
+        let position = Vector3::new(1.0, 2.0, 3.0);
+        assert_eq!(Vector4::point(position).w, 1.0);
+        assert_eq!(Vector4::direction(position).w, 0.0);
+        assert_eq!(Vector4::new(2.0, 4.0, 6.0, 2.0).perspective_divide(), position);
+        assert_eq!(Vector4::direction(position).perspective_divide(), position);
+    }
+
+    #[test]
+    fn component_ops() {
+        let a = Vector4::new(1.0, -2.0, 3.0, 0.0);
+        let b = Vector4::new(3.0, 2.0, 1.0, 4.0);
+        assert_eq!(a + b, Vector4::new(4.0, 0.0, 4.0, 4.0));
+        assert_eq!(a - b, Vector4::new(-2.0, -4.0, 2.0, -4.0));
+        assert_eq!(a * b, Vector4::new(3.0, -4.0, 3.0, 0.0));
+        assert_eq!(-a, Vector4::new(-1.0, 2.0, -3.0, 0.0));
+        assert_eq!(a.lerp(b, 0.5), Vector4::new(2.0, 0.0, 2.0, 2.0));
+    }
+}
@@ -126,4 +126,16 @@ impl<G: 'static> GameLoop<G> {
 
         true
     }
+
+    pub fn last_frame_time(&self) -> f64 {
+        self.last_frame_time
+    }
+
+    pub fn running_time(&self) -> f64 {
+        self.running_time
+    }
+
+    pub fn fixed_time_step(&self) -> f64 {
+        self.fixed_time_step
+    }
 }
\ No newline at end of file
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use hexgen_common::grid::Grid;
+use hexgen_common::vector3::Vector3;
+use crate::map::{cell_distance, ring, Terrain, TerrainMap};
+
+// Starts may end up this many of a resource apart after balancing
+const BALANCE_TOLERANCE: usize = 1;
+
+// Something placed on a cell on top of its terrain, a resource or a point of interest
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Feature {
+    Ore,
+    Lumber,
+    Fish,
+    Ruins,
+    Cave,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 5] = [Feature::Ore, Feature::Lumber, Feature::Fish, Feature::Ruins, Feature::Cave];
+
+    // Resources can be gathered, the rest are points of interest
+    pub fn is_resource(&self) -> bool {
+        matches!(self, Feature::Ore | Feature::Lumber | Feature::Fish)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::Ore => "Ore",
+            Feature::Lumber => "Lumber",
+            Feature::Fish => "Fish",
+            Feature::Ruins => "Ruins",
+            Feature::Cave => "Cave",
+        }
+    }
+
+    // Colour of the marker drawn on the cell
+    pub fn color(&self) -> Vector3 {
+        match self {
+            Feature::Ore => Vector3::new(0.75, 0.45, 0.2),
+            Feature::Lumber => Vector3::new(0.15, 0.35, 0.1),
+            Feature::Fish => Vector3::new(0.85, 0.85, 0.9),
+            Feature::Ruins => Vector3::new(0.7, 0.65, 0.5),
+            Feature::Cave => Vector3::new(0.15, 0.12, 0.12),
+        }
+    }
+}
+
+// Where and how often one feature is placed
+#[derive(Clone, Debug)]
+pub struct PlacementRule {
+    pub feature: Feature,
+    // Terrain the feature can stand on
+    pub terrains: Vec<Terrain>,
+    // Additionally one of these terrains has to lie within the given number of steps
+    pub near: Option<(Vec<Terrain>, u32)>,
+    // Share of the allowed cells the pass aims for, the spacing can leave it short
+    pub density: f32,
+    // Fewest steps between two features of this rule
+    pub min_distance: u32,
+}
+
+impl PlacementRule {
+    pub fn new(feature: Feature, terrains: &[Terrain], density: f32, min_distance: u32) -> PlacementRule {
+        PlacementRule {
+            feature,
+            terrains: terrains.to_vec(),
+            near: None,
+            density,
+            min_distance,
+        }
+    }
+
+    pub fn near(mut self, terrains: &[Terrain], distance: u32) -> PlacementRule {
+        self.near = Some((terrains.to_vec(), distance));
+        self
+    }
+
+    pub fn allows(&self, map: &TerrainMap, cell: (u8, u8)) -> bool {
+        let terrain = match map.get(cell.0, cell.1) {
+            Some(cell) => cell.terrain,
+            None => return false,
+        };
+        if !self.terrains.contains(&terrain) {
+            return false;
+        }
+        match &self.near {
+            Some((terrains, distance)) => (0..=*distance as i32)
+                .flat_map(|radius| ring((cell.0 as i32, cell.1 as i32), radius))
+                .any(|(x, z)| map.contains(x, z) && terrains.contains(&map.get(x as u8, z as u8).unwrap().terrain)),
+            None => true,
+        }
+    }
+}
+
+// Ore on stone hills, lumber in forests, fish by the rocks in the water, ruins and caves as points of interest
+pub fn default_rules() -> Vec<PlacementRule> {
+    vec![
+        PlacementRule::new(Feature::Ore, &[Terrain::StoneHill], 0.5, 2),
+        PlacementRule::new(Feature::Lumber, &[Terrain::DirtLumber, Terrain::GrassForest], 0.4, 2),
+        PlacementRule::new(Feature::Fish, &[Terrain::Water, Terrain::WaterRocks], 0.3, 3).near(&[Terrain::WaterRocks], 1),
+        PlacementRule::new(Feature::Cave, &[Terrain::Stone, Terrain::StoneHill], 0.1, 5).near(&[Terrain::StoneMountain], 1),
+        PlacementRule::new(Feature::Ruins, &[Terrain::Dirt, Terrain::Grass, Terrain::Stone], 0.03, 6),
+    ]
+}
+
+// At most one feature per cell
+#[derive(Clone, Debug)]
+pub struct FeatureMap {
+    cells: Grid<Option<Feature>>,
+}
+
+impl FeatureMap {
+    pub fn new(width: u8, height: u8) -> FeatureMap {
+        FeatureMap {
+            cells: Grid::new(width, height, None),
+        }
+    }
+
+    pub fn get(&self, x: u8, z: u8) -> Option<Feature> {
+        self.cells.get(x, z).copied().flatten()
+    }
+
+    // False when the cell is outside the map or already taken
+    pub fn insert(&mut self, cell: (u8, u8), feature: Feature) -> bool {
+        match self.cells.get_mut(cell.0, cell.1) {
+            Some(placed) if placed.is_none() => {
+                *placed = Some(feature);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove(&mut self, cell: (u8, u8)) -> Option<Feature> {
+        self.cells.get_mut(cell.0, cell.1).and_then(|placed| placed.take())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((u8, u8), Feature)> + '_ {
+        self.cells.iter().filter_map(|(cell, feature)| feature.map(|feature| (cell, feature)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.values().iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn count(&self, feature: Feature) -> usize {
+        self.iter().filter(|(_, placed)| *placed == feature).count()
+    }
+
+    // Features within radius steps of center, by kind
+    pub fn count_within(&self, center: (u8, u8), radius: u32) -> HashMap<Feature, usize> {
+        let mut counts = HashMap::new();
+        for (_, feature) in self.iter().filter(|(cell, _)| cell_distance(center, *cell) <= radius) {
+            *counts.entry(feature).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // No other feature of the kind closer than min_distance steps
+    fn is_spaced(&self, cell: (u8, u8), feature: Feature, min_distance: u32) -> bool {
+        self.iter().all(|(other, placed)| placed != feature || cell_distance(cell, other) >= min_distance)
+    }
+}
+
+// Runs the rules in order, earlier rules get first pick of the cells. Each rule is Poisson-disk sampled
+// by dart throwing: allowed cells are tried in random order and kept only when no feature of the rule
+// lies within its minimum distance, which spreads features evenly without a visible pattern
+pub fn place_features<R: Rng + ?Sized>(map: &TerrainMap, rules: &[PlacementRule], rng: &mut R) -> FeatureMap {
+    let mut features = FeatureMap::new(map.width(), map.height());
+    for rule in rules {
+        let mut candidates: Vec<(u8, u8)> = map.cells()
+            .map(|(cell, _)| cell)
+            .filter(|cell| features.get(cell.0, cell.1).is_none() && rule.allows(map, *cell))
+            .collect();
+        let target = (candidates.len() as f32 * rule.density).round() as usize;
+        candidates.shuffle(rng);
+
+        let mut placed = 0;
+        for cell in candidates {
+            if placed >= target {
+                break;
+            }
+            if features.is_spaced(cell, rule.feature, rule.min_distance) && features.insert(cell, rule.feature) {
+                placed += 1;
+            }
+        }
+    }
+    features
+}
+
+// Evens out the resources within radius steps of every start. Starts short of the richest one get extra
+// resources where the rules allow them, and where that isn't enough the richer starts give up resources
+// no other start can reach. Returns the resources around each start afterwards
+pub fn balance_starts<R: Rng + ?Sized>(
+    map: &TerrainMap,
+    rules: &[PlacementRule],
+    features: &mut FeatureMap,
+    starts: &[(u8, u8)],
+    radius: u32,
+    rng: &mut R,
+) -> Vec<HashMap<Feature, usize>> {
+    for rule in rules.iter().filter(|rule| rule.feature.is_resource()) {
+        let feature = rule.feature;
+        let count = |features: &FeatureMap, start: (u8, u8)| {
+            features.count_within(start, radius).get(&feature).copied().unwrap_or(0)
+        };
+
+        let target = starts.iter().map(|start| count(features, *start)).max().unwrap_or(0);
+        for start in starts {
+            let mut candidates: Vec<(u8, u8)> = map.cells()
+                .map(|(cell, _)| cell)
+                .filter(|cell| cell_distance(*start, *cell) <= radius)
+                .filter(|cell| features.get(cell.0, cell.1).is_none() && rule.allows(map, *cell))
+                .collect();
+            candidates.shuffle(rng);
+            let mut current = count(features, *start);
+            for cell in candidates {
+                if current >= target {
+                    break;
+                }
+                if features.is_spaced(cell, feature, rule.min_distance) && features.insert(cell, feature) {
+                    current += 1;
+                }
+            }
+        }
+
+        let floor = starts.iter().map(|start| count(features, *start)).min().unwrap_or(0);
+        for (index, start) in starts.iter().enumerate() {
+            // Only resources out of every other start's reach, so no other start loses any
+            let mut exclusive: Vec<(u8, u8)> = features.iter()
+                .filter(|(cell, placed)| *placed == feature && cell_distance(*start, *cell) <= radius)
+                .map(|(cell, _)| cell)
+                .filter(|cell| starts.iter().enumerate().all(|(other, other_start)| other == index || cell_distance(*other_start, *cell) > radius))
+                .collect();
+            exclusive.shuffle(rng);
+            let mut current = count(features, *start);
+            for cell in exclusive {
+                if current <= floor + BALANCE_TOLERANCE {
+                    break;
+                }
+                features.remove(cell);
+                current -= 1;
+            }
+        }
+    }
+    starts.iter().map(|start| features.count_within(*start, radius)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::map::MapCell;
+
+    // Every terrain mixed evenly, so each default rule has plenty of room
+    fn mixed_map(width: u8, height: u8, seed: u64) -> TerrainMap {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut map = TerrainMap::new(width, height);
+        for x in 0..width {
+            for z in 0..height {
+                let terrain = *Terrain::ALL.choose(&mut rng).unwrap();
+                map.set(x, z, MapCell { terrain, elevation: if terrain.is_water() { 0.0 } else { 0.1 } });
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn features_keep_their_spacing() {
+        let map = mixed_map(30, 30, 3);
+        let rules = default_rules();
+        let features = place_features(&map, &rules, &mut StdRng::seed_from_u64(7));
+        for rule in &rules {
+            let placed: Vec<(u8, u8)> = features.iter().filter(|(_, feature)| *feature == rule.feature).map(|(cell, _)| cell).collect();
+            assert!(!placed.is_empty(), "no {} placed", rule.feature.name());
+            for (index, cell) in placed.iter().enumerate() {
+                assert!(rule.allows(&map, *cell));
+                for other in &placed[index + 1..] {
+                    assert!(cell_distance(*cell, *other) >= rule.min_distance, "{} at {:?} and {:?}", rule.feature.name(), cell, other);
+                }
+            }
+        }
+        // The same seed places the same features
+        let again = place_features(&map, &rules, &mut StdRng::seed_from_u64(7));
+        assert_eq!(features.iter().collect::<Vec<_>>(), again.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn balanced_starts_get_comparable_resources() {
+        let map = mixed_map(30, 30, 3);
+        let rules = default_rules();
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut features = place_features(&map, &rules, &mut rng);
+        let starts = [(4, 4), (25, 4), (4, 25), (25, 25), (15, 15)];
+        let radius = 3;
+        let spread = |counts: &[HashMap<Feature, usize>], feature: Feature| {
+            let amounts: Vec<usize> = counts.iter().map(|count| count.get(&feature).copied().unwrap_or(0)).collect();
+            amounts.iter().max().unwrap() - amounts.iter().min().unwrap()
+        };
+        let before: Vec<HashMap<Feature, usize>> = starts.iter().map(|start| features.count_within(*start, radius)).collect();
+        assert!(Feature::ALL.iter().any(|feature| spread(&before, *feature) > BALANCE_TOLERANCE));
+
+        let counts = balance_starts(&map, &rules, &mut features, &starts, radius, &mut rng);
+
+        for (start, count) in starts.iter().zip(&counts) {
+            assert_eq!(*count, features.count_within(*start, radius));
+        }
+        for feature in Feature::ALL.iter().filter(|feature| feature.is_resource()) {
+            assert!(spread(&counts, *feature) <= BALANCE_TOLERANCE, "{} around the starts: {:?}", feature.name(), counts);
+        }
+        // Balancing keeps the spacing
+        for rule in &rules {
+            let placed: Vec<(u8, u8)> = features.iter().filter(|(_, feature)| *feature == rule.feature).map(|(cell, _)| cell).collect();
+            for (index, cell) in placed.iter().enumerate() {
+                assert!(placed[index + 1..].iter().all(|other| cell_distance(*cell, *other) >= rule.min_distance));
+            }
+        }
+    }
+}
@@ -1,131 +1,525 @@
+pub mod features;
+pub mod map;
+pub mod pathfinding;
+pub mod regions;
+pub mod starts;
 pub mod ui;
+pub mod visibility;
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use glium::Display;
 use glium::glutin::surface::WindowSurface;
 use noise::{Fbm, Perlin};
 use noise::utils::{NoiseMapBuilder, PlaneMapBuilder};
+use hexgen_common::asset_manager::AssetManager;
+use hexgen_common::ecs::components::{Renderable, SceneNode};
 use hexgen_common::game_object::GameObject;
+use hexgen_common::material::overrides::MaterialOverrides;
 use hexgen_common::model::Model;
-use hexgen_common::transform::Translation;
+use hexgen_common::model::mesh::builder::{HeightmapTerrain, HexPrism};
+use hexgen_common::scene::{NodeId, Scene};
+use hexgen_common::transform::{Scale, Translation};
 use hexgen_common::vector3::Vector3;
+use hexgen_renderer::camera::controller::CameraController;
+use crate::map::{cell_at, neighbor, surface_height, tile_center, MapCell, Terrain, TerrainMap, TileClass};
 use hexgen_renderer::renderer::Renderer;
+use crate::features::{default_rules, Feature, FeatureMap, PlacementRule};
+use crate::regions::RegionMap;
+use crate::starts::{populate, MapSeeds, StartPlacement, StartSettings};
+use crate::visibility::{field_of_view, Sight};
+use hexgen_renderer::time_of_day::TimeOfDay;
 use rand;
 use rand::{Rng, SeedableRng};
+use tracing::error;
 
 pub struct Generator<'a> {
-    pub game_objects: Vec<GameObject>,
+    pub scene: Scene,
+    // Terrain and elevation of every cell of the current map, used by gameplay queries
+    pub map: TerrainMap,
+    // Regions of map, recalculated with every new map
+    pub regions: RegionMap,
+    // Resources and points of interest, placed by placement_rules after the terrain
+    pub features: FeatureMap,
+    pub placement_rules: Vec<PlacementRule>,
+    pub start_settings: StartSettings,
+    // None when the map has no land area big enough for the players
+    pub starts: Option<StartPlacement>,
+    // Features and starts of the current map are placed from these
+    seeds: MapSeeds,
+    // Parent of the feature and start markers
+    marker_root: Option<NodeId>,
+    // Chunk node of every CHUNK_SIZE square of tiles in the current map
+    chunks: HashMap<(u8, u8), NodeId>,
     pub models: Vec<Rc<RefCell<Model>>>,
     pub renderer: Renderer<'a>,
+    pub camera_controller: CameraController,
+    pub time_of_day: TimeOfDay,
+    pub assets: AssetManager,
+    pub terrain_style: TerrainStyle,
+    // Sight of the observer at the camera focus, which reveals the map when fog of war is on
+    pub sight: Sight,
+    // Cell the fog of war was last updated from, None forces an update on the next tick
+    pub observer: Option<(u8, u8)>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TerrainStyle {
+    // Stacked OBJ tiles
+    Models,
+    // Procedural hex prisms with continuous heights
+    Prisms,
+    // One smooth surface through the tile heights
+    Smooth,
+}
+
+// Attached to every tile entity, the map cell the tile stands on
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TerrainTile {
+    pub x: u8,
+    pub z: u8,
+}
+
+// Tiles per side of the square chunks the map is grouped into
+const CHUNK_SIZE: u8 = 8;
+
+// Height of one stacked OBJ tile, stacks step up by it
+const TILE_HEIGHT: f32 = 0.1;
+
+// Procedural tile heights are rounded to this so prisms of similar height share a model
+const PRISM_HEIGHT_STEP: f32 = 0.025;
+
+// Feature markers are tile prisms shrunk to this share of the tile width
+const FEATURE_MARKER_SCALE: f32 = 0.25;
+const FEATURE_MARKER_HEIGHT: f32 = 0.2;
+const START_MARKER_SCALE: f32 = 0.4;
+const START_MARKER_HEIGHT: f32 = 0.6;
+
+// Start marker colours, players past the end of the list reuse them
+const PLAYER_COLORS: [Vector3; 8] = [
+    Vector3 { x: 0.85, y: 0.15, z: 0.15 },
+    Vector3 { x: 0.15, y: 0.35, z: 0.85 },
+    Vector3 { x: 0.95, y: 0.8, z: 0.15 },
+    Vector3 { x: 0.55, y: 0.2, z: 0.75 },
+    Vector3 { x: 0.1, y: 0.75, z: 0.75 },
+    Vector3 { x: 0.95, y: 0.5, z: 0.1 },
+    Vector3 { x: 0.95, y: 0.95, z: 0.95 },
+    Vector3 { x: 0.9, y: 0.4, z: 0.65 },
+];
+
+// Terrain palette, generate_terrain picks models by their index in this list
+const TERRAIN_MODELS: [&str; 10] = [
+    "./res/models/water.obj",
+    "./res/models/water_rocks.obj",
+    "./res/models/dirt.obj",
+    "./res/models/dirt_lumber.obj",
+    "./res/models/grass.obj",
+    "./res/models/grass_forest.obj",
+    "./res/models/grass_hill.obj",
+    "./res/models/stone.obj",
+    "./res/models/stone_hill.obj",
+    "./res/models/stone_mountain.obj",
+];
+
 impl<'a> Generator<'a> {
     pub fn new(renderer: Renderer) -> Generator {
+        let camera_controller = CameraController::new(&renderer.camera, 6.0);
         Generator {
-            game_objects: vec![],
+            scene: Scene::new(),
+            map: TerrainMap::new(0, 0),
+            regions: RegionMap::new(&TerrainMap::new(0, 0)),
+            features: FeatureMap::new(0, 0),
+            placement_rules: default_rules(),
+            start_settings: StartSettings::default(),
+            starts: None,
+            seeds: MapSeeds::default(),
+            marker_root: None,
+            chunks: HashMap::new(),
             models: vec![],
             renderer,
+            camera_controller,
+            time_of_day: TimeOfDay::new(),
+            assets: AssetManager::new(),
+            terrain_style: TerrainStyle::Models,
+            sight: Sight::default(),
+            observer: None,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.time_of_day.enabled {
+            self.time_of_day.update(delta_time);
+            self.time_of_day.apply(&mut self.renderer);
+        }
+        if self.renderer.fog_of_war.enabled {
+            self.update_fog_of_war();
+        }
+    }
+
+    // Reveals what can be seen from the cell under the camera focus, only when that cell changes
+    fn update_fog_of_war(&mut self) {
+        let focus = self.camera_controller.focus(&self.renderer.camera);
+        let (x, z) = cell_at(focus.x, focus.z);
+        if !self.map.contains(x, z) {
+            return;
+        }
+        let cell = (x as u8, z as u8);
+        if self.observer == Some(cell) {
+            return;
+        }
+        self.observer = Some(cell);
+        let visible = field_of_view(&self.map, &self.sight, cell);
+        self.renderer.fog_of_war.update_visible(visible);
+    }
+
+    // Recompiles shaders edited on disk and swaps them into every material and pass using them
+    pub fn reload_shaders(&mut self, display: &Display<WindowSurface>) {
+        let reloaded = self.assets.reload_changed_shaders(display);
+        if !reloaded.is_empty() {
+            self.renderer.swap_programs(&reloaded);
         }
     }
 
     pub fn init_scene(&mut self, display: &Display<WindowSurface>) {
-        let mut water_model = Model::new(String::from("Water model"));
-        water_model.load_model("./res/models/water.obj", display);
-        self.models.push(Rc::new(RefCell::new(water_model)));
-        let mut water_rocks_model = Model::new(String::from("Water rocks model"));
-        water_rocks_model.load_model("./res/models/water_rocks.obj", display);
-        self.models.push(Rc::new(RefCell::new(water_rocks_model)));
-
-
-        let mut dirt_model = Model::new(String::from("Dirt model"));
-        dirt_model.load_model("./res/models/dirt.obj", display);
-        self.models.push(Rc::new(RefCell::new(dirt_model)));
-        let mut dirt_lumber_model = Model::new(String::from("Dirt lumber model"));
-        dirt_lumber_model.load_model("./res/models/dirt_lumber.obj", display);
-        self.models.push(Rc::new(RefCell::new(dirt_lumber_model)));
-
-        let mut grass_model = Model::new(String::from("Grass model"));
-        grass_model.load_model("./res/models/grass.obj", display);
-        self.models.push(Rc::new(RefCell::new(grass_model)));
-        let mut grass_forest_model = Model::new(String::from("Grass forest model"));
-        grass_forest_model.load_model("./res/models/grass_forest.obj", display);
-        self.models.push(Rc::new(RefCell::new(grass_forest_model)));
-        let mut grass_hill_model = Model::new(String::from("Grass hill model"));
-        grass_hill_model.load_model("./res/models/grass_hill.obj", display);
-        self.models.push(Rc::new(RefCell::new(grass_hill_model)));
-
-        let mut stone_model = Model::new(String::from("Stone model"));
-        stone_model.load_model("./res/models/stone.obj", display);
-        self.models.push(Rc::new(RefCell::new(stone_model)));
-        let mut stone_hill_model = Model::new(String::from("Stone hill model"));
-        stone_hill_model.load_model("./res/models/stone_hill.obj", display);
-        self.models.push(Rc::new(RefCell::new(stone_hill_model)));
-        let mut stone_mountain_model = Model::new(String::from("Stone mountain model"));
-        stone_mountain_model.load_model("./res/models/stone_mountain.obj", display);
-        self.models.push(Rc::new(RefCell::new(stone_mountain_model)));
-
-        self.generate_terrain_without_seed(5, 5);
-    }
-
-    pub fn generate_terrain_with_seed(&mut self, width: u8, height: u8, seed: u64){
+        self.renderer.load(display, &mut self.assets.shaders);
+        self.assets.set_material_overrides(MaterialOverrides::load("./res/materials.cfg"));
+
+        let mut models_missing = false;
+        for path in TERRAIN_MODELS {
+            // Missing models are drawn as a placeholder so the palette indices stay stable
+            let model = match self.assets.load_model(path, display) {
+                Ok(model) => model,
+                Err(_) => {
+                    models_missing = true;
+                    self.assets.missing_model(display)
+                }
+            };
+            self.models.push(model);
+        }
+        // Without the tile models the procedural prisms still give a usable map
+        if models_missing {
+            self.terrain_style = TerrainStyle::Prisms;
+        }
+        self.assets.shaders.log_stats();
+        self.assets.watch_shaders("./res/shaders");
+
+        self.generate_terrain_without_seed(5, 5, display);
+        self.frame_terrain();
+    }
+
+    pub fn terrain_bounds(&self) -> (Vector3, Vector3) {
+        let mut bounds: Option<(Vector3, Vector3)> = None;
+        for (_, renderable, node) in self.scene.world.query2::<Renderable, SceneNode>() {
+            let world_matrix = match self.scene.world_matrix(node.0) {
+                Some(world_matrix) => world_matrix,
+                None => continue,
+            };
+            let (low, high) = match renderable.model.borrow().bounds() {
+                Some((low, high)) => (world_matrix.transform_point(low), world_matrix.transform_point(high)),
+                None => continue,
+            };
+            let (low, high) = (low.min(high), low.max(high));
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.min(low), max.max(high)),
+                None => (low, high),
+            });
+        }
+        bounds.unwrap_or((Vector3::zero(), Vector3::zero()))
+    }
+
+    pub fn frame_terrain(&mut self) {
+        let (min, max) = self.terrain_bounds();
+        self.camera_controller.frame(min, max, self.renderer.camera.projection.as_ref());
+    }
+
+    pub fn generate_terrain_with_seed(&mut self, width: u8, height: u8, seed: u64, display: &Display<WindowSurface>){
         let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
         let fbm = Fbm::<Perlin>::new(rng.gen_range(0, 100));
         let noise = PlaneMapBuilder::<_, 2>::new(&fbm).set_size(width as usize, height as usize)
             .set_x_bounds(-5.0, 5.0)
             .set_y_bounds(-5.0, 5.0)
             .build();
-        self.generate_terrain(rng, noise, width, height);
+        self.generate_terrain(rng, noise, width, height, display);
     }
 
-    pub fn generate_terrain_without_seed(&mut self, width: u8, height: u8){
+    pub fn generate_terrain_without_seed(&mut self, width: u8, height: u8, display: &Display<WindowSurface>){
         let mut rng = rand::rngs::StdRng::from_entropy();
         let fbm = Fbm::<Perlin>::new(rng.gen_range(0, 100));
         let noise = PlaneMapBuilder::<_, 2>::new(&fbm).set_size(width as usize, height as usize)
             .set_x_bounds(-5.0, 5.0)
             .set_y_bounds(-5.0, 5.0)
             .build();
-        self.generate_terrain(rng, noise, width, height);
+        self.generate_terrain(rng, noise, width, height, display);
+    }
+
+    fn generate_terrain(&mut self, mut rng: rand::rngs::StdRng, noise: noise::utils::NoiseMap, width: u8, height: u8, display: &Display<WindowSurface>) {
+        self.scene.clear();
+        self.chunks.clear();
+        self.map = TerrainMap::new(width, height);
+        self.renderer.fog_of_war.resize(display, width, height);
+        self.observer = None;
+        for z in 0..height {
+            for x in 0..width {
+                let noise_value = noise.get_value(x as usize, z as usize);
+                let terrain = TileClass::from_noise(noise_value).plain_terrain();
+                self.map.set(x, z, MapCell { terrain, elevation: surface_height(noise_value) });
+            }
+        }
+        // Placement records the terrain and elevation of the tiles it actually builds over these
+        let mut water_tiles: Vec<(NodeId, u8, u8)> = vec![];
+        match self.terrain_style {
+            TerrainStyle::Models => self.place_model_tiles(&mut rng, &noise, width, height, &mut water_tiles),
+            TerrainStyle::Prisms => self.place_prism_tiles(&mut rng, &noise, width, height, display, &mut water_tiles),
+            TerrainStyle::Smooth => self.place_smooth_terrain(&mut rng, &noise, width, height, display),
+        }
+        // After placement, which decides the final terrain of every cell
+        self.regions = self.map.regions();
+        self.seeds = MapSeeds::new(&mut rng);
+        self.marker_root = None;
+        self.populate(display);
+
+        let is_water = |x: i32, z: i32| {
+            x < 0 || z < 0 || x >= width as i32 || z >= height as i32
+                || noise.get_value(x as usize, z as usize) < -0.3
+        };
+        for (id, x, z) in water_tiles {
+            let mut shore_mask = 0u8;
+            for direction in 0..6 {
+                let (nx, nz) = neighbor(x as i32, z as i32, direction);
+                if !is_water(nx, nz) {
+                    shore_mask |= 1 << direction;
+                }
+            }
+            if let Some(renderable) = self.scene.entity(id).and_then(|entity| self.scene.world.get_mut::<Renderable>(entity)) {
+                renderable.shore_mask = shore_mask;
+            }
+        }
+        self.scene.update_world_matrices();
+    }
+
+    // Tiles are grouped under one node per chunk so a whole chunk can be moved or removed at once
+    fn add_tile(&mut self, x: u8, z: u8, go: GameObject, model: Rc<RefCell<Model>>) -> NodeId {
+        let key = (x / CHUNK_SIZE, z / CHUNK_SIZE);
+        let chunk = match self.chunks.get(&key) {
+            Some(chunk) => *chunk,
+            None => {
+                let chunk = self.scene.add(GameObject::new(format!("Chunk {},{}", key.0, key.1)));
+                self.chunks.insert(key, chunk);
+                chunk
+            }
+        };
+        let id = self.scene.add_child(chunk, go);
+        if let Some(entity) = self.scene.entity(id) {
+            self.scene.world.insert(entity, Renderable::new(model));
+            self.scene.world.insert(entity, TerrainTile { x, z });
+        }
+        id
     }
 
-    fn generate_terrain(&mut self, mut rng: rand::rngs::StdRng, noise: noise::utils::NoiseMap, width: u8, height: u8) {
-        self.game_objects.clear();
+    // The top tile of a stack decides the terrain of its cell, index is into TERRAIN_MODELS.
+    // top is the height of the tile's upper face, the cell's elevation
+    fn record_terrain(&mut self, x: u8, z: u8, index: usize, top: f32) {
+        if let Some(cell) = self.map.get_mut(x, z) {
+            cell.terrain = Terrain::from_palette_index(index);
+            cell.elevation = top;
+        }
+    }
 
+    fn place_model_tiles(&mut self, rng: &mut rand::rngs::StdRng, noise: &noise::utils::NoiseMap, width: u8, height: u8, water_tiles: &mut Vec<(NodeId, u8, u8)>) {
         for z in 0..height {
             for x in 0..width {
                 let noise_value = noise.get_value(x as usize, z as usize);
                 if noise_value < -0.3 {
                     let val = rng.gen_range(0.0, 1.0);
-                    let mut go = GameObject::new(String::from(format!("{} {},{}", "Water", x, z)), self.models[if val < 0.75 {0} else {1}].clone());
-                    go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, noise_value as f32 / 12.0, x as f32 / 1.2));
-                    self.game_objects.push(go);
+                    let index = if val < 0.75 {0} else {1};
+                    let model = self.models[index].clone();
+                    let y = noise_value as f32 / 12.0;
+                    self.record_terrain(x, z, index, y + TILE_HEIGHT);
+                    let mut go = GameObject::new(String::from(format!("{} {},{}", "Water", x, z)));
+                    go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, y, x as f32 / 1.2));
+                    let id = self.add_tile(x, z, go, model);
+                    water_tiles.push((id, x, z));
                 } else if noise_value < 0.0 {
                     for i in 0..(1+((1.0+noise_value)*3.0f64).round() as i32) {
-                        let mut go = GameObject::new(String::from(format!("{} {},{}", "Dirt", x, z)), self.models[2+rng.gen_range(0,2)].clone());
-                        go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, (i as f32)*0.1f32+noise_value as f32 / 12.0, x as f32 / 1.2));
-                        self.game_objects.push(go);
+                        let index = 2+rng.gen_range(0,2);
+                        let model = self.models[index].clone();
+                        let y = (i as f32)*0.1f32+noise_value as f32 / 12.0;
+                        self.record_terrain(x, z, index, y + TILE_HEIGHT);
+                        let mut go = GameObject::new(String::from(format!("{} {},{}", "Dirt", x, z)));
+                        go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, y, x as f32 / 1.2));
+                        self.add_tile(x, z, go, model);
                     }
                 } else if noise_value < 0.6 {
                     for i in 0..(((1.0+noise_value)*3.0f64).round() as i32) {
-                        let mut go = GameObject::new(String::from(format!("{} {},{}", "Dirt", x, z)), self.models[2+rng.gen_range(0,2)].clone());
+                        let model = self.models[2+rng.gen_range(0,2)].clone();
+                        let mut go = GameObject::new(String::from(format!("{} {},{}", "Dirt", x, z)));
                         go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, (i as f32)*0.1f32+noise_value as f32 / 12.0, x as f32 / 1.2));
-                        self.game_objects.push(go);
+                        self.add_tile(x, z, go, model);
                     }
-                    let mut go = GameObject::new(String::from(format!("{} {},{}", "Grass", x, z)), self.models[4+rng.gen_range(0,3)].clone());
-                    go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, ((((1.0+noise_value)*3.0f64).round() as i32) as f32)*0.1f32+noise_value as f32 / 12.0, x as f32 / 1.2));
-                    self.game_objects.push(go);
+                    let index = 4+rng.gen_range(0,3);
+                    let model = self.models[index].clone();
+                    let y = ((((1.0+noise_value)*3.0f64).round() as i32) as f32)*0.1f32+noise_value as f32 / 12.0;
+                    self.record_terrain(x, z, index, y + TILE_HEIGHT);
+                    let mut go = GameObject::new(String::from(format!("{} {},{}", "Grass", x, z)));
+                    go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, y, x as f32 / 1.2));
+                    self.add_tile(x, z, go, model);
                 } else {
                     for i in 0..(((1.0+noise_value)*3.0f64).round() as i32) {
-                        let mut go = GameObject::new(String::from(format!("{} {},{}", "Dirt", x, z)), self.models[2+rng.gen_range(0,2)].clone());
+                        let model = self.models[2+rng.gen_range(0,2)].clone();
+                        let mut go = GameObject::new(String::from(format!("{} {},{}", "Dirt", x, z)));
                         go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, (i as f32)*0.1f32+noise_value as f32 / 12.0, x as f32 / 1.2));
-                        self.game_objects.push(go);
+                        self.add_tile(x, z, go, model);
                     }
-                    let mut go = GameObject::new(String::from(format!("{} {},{}", "Stone", x, z)), self.models[7+rng.gen_range(0,3)].clone());
-                    go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, ((((1.0+noise_value)*3.0f64).round() as i32) as f32)*0.1f32+noise_value as f32 / 12.0, x as f32 / 1.2));
-                    self.game_objects.push(go);
+                    let index = 7+rng.gen_range(0,3);
+                    let model = self.models[index].clone();
+                    let y = ((((1.0+noise_value)*3.0f64).round() as i32) as f32)*0.1f32+noise_value as f32 / 12.0;
+                    self.record_terrain(x, z, index, y + TILE_HEIGHT);
+                    let mut go = GameObject::new(String::from(format!("{} {},{}", "Stone", x, z)));
+                    go.translate(Vector3::new((x % 2) as f32 / 2.0 + z as f32, y, x as f32 / 1.2));
+                    self.add_tile(x, z, go, model);
                 }
             }
         }
     }
-}
 
+    fn place_prism_tiles(&mut self, rng: &mut rand::rngs::StdRng, noise: &noise::utils::NoiseMap, width: u8, height: u8, display: &Display<WindowSurface>, water_tiles: &mut Vec<(NodeId, u8, u8)>) {
+        for z in 0..height {
+            for x in 0..width {
+                let noise_value = noise.get_value(x as usize, z as usize);
+                let class = TileClass::from_noise(noise_value);
+                let terrain = class.variant(rng);
+                let (center_x, center_z) = tile_center(x, z);
+                // Water keeps its thin tile and sits at the noise level like the OBJ water
+                let (base, top) = match class {
+                    TileClass::Water => (noise_value as f32 / 12.0, 0.1),
+                    _ => (0.0, surface_height(noise_value)),
+                };
+                let steps = (top / PRISM_HEIGHT_STEP).round().max(1.0) as u32;
+                self.record_terrain(x, z, terrain.palette_index(), base + steps as f32 * PRISM_HEIGHT_STEP);
+                let model = self.prism_model(terrain, steps, display);
+                let mut go = GameObject::new(format!("{} {},{}", terrain.name(), x, z));
+                go.translate(Vector3::new(center_x, base, center_z));
+                let id = self.add_tile(x, z, go, model);
+                if class == TileClass::Water {
+                    water_tiles.push((id, x, z));
+                }
+            }
+        }
+    }
+
+    // Picks other starts on the same map, the same seed and roll always give the same starts
+    pub fn reroll_starts(&mut self, display: &Display<WindowSurface>) {
+        self.seeds.reroll();
+        self.populate(display);
+    }
+
+    // Places the features and starts for the current seeds and marks them
+    pub fn populate(&mut self, display: &Display<WindowSurface>) {
+        if let Some(root) = self.marker_root.take() {
+            self.scene.remove(root);
+        }
+        let (features, starts) = populate(&self.map, &self.placement_rules, &self.start_settings, &self.seeds);
+        self.features = features;
+        self.starts = starts;
+        self.place_markers(display);
+        self.scene.update_world_matrices();
+    }
+
+    // A small pillar in the feature's colour on every cell holding a feature, a taller one in the player's colour on every start
+    fn place_markers(&mut self, display: &Display<WindowSurface>) {
+        let root = self.scene.add(GameObject::new(String::from("Markers")));
+        self.marker_root = Some(root);
+        let placed: Vec<((u8, u8), Feature)> = self.features.iter().collect();
+        for ((x, z), feature) in placed {
+            let model = self.feature_model(feature, display);
+            let (center_x, center_z) = tile_center(x, z);
+            let elevation = self.map.get(x, z).map_or(0.0, |cell| cell.elevation);
+            let mut go = GameObject::new(format!("{} {},{}", feature.name(), x, z));
+            go.scale(Vector3::new(FEATURE_MARKER_SCALE, 1.0, FEATURE_MARKER_SCALE));
+            go.translate(Vector3::new(center_x, elevation, center_z));
+            self.add_marker(root, go, model);
+        }
+
+        let starts = self.starts.as_ref().map_or(vec![], |placement| placement.starts.clone());
+        for (player, (x, z)) in starts.into_iter().enumerate() {
+            let model = self.start_model(player, display);
+            let (center_x, center_z) = tile_center(x, z);
+            let elevation = self.map.get(x, z).map_or(0.0, |cell| cell.elevation);
+            let mut go = GameObject::new(format!("Start {} {},{}", player + 1, x, z));
+            go.scale(Vector3::new(START_MARKER_SCALE, 1.0, START_MARKER_SCALE));
+            go.translate(Vector3::new(center_x, elevation, center_z));
+            self.add_marker(root, go, model);
+        }
+    }
+
+    fn add_marker(&mut self, root: NodeId, go: GameObject, model: Rc<RefCell<Model>>) {
+        let id = self.scene.add_child(root, go);
+        if let Some(entity) = self.scene.entity(id) {
+            self.scene.world.insert(entity, Renderable::new(model));
+        }
+    }
+
+    fn start_model(&mut self, player: usize, display: &Display<WindowSurface>) -> Rc<RefCell<Model>> {
+        let player = player % PLAYER_COLORS.len();
+        let key = format!("procedural/start/{}", player);
+        let color = PLAYER_COLORS[player];
+        self.prism_asset(&key, HexPrism::new(format!("start_{}", player), START_MARKER_HEIGHT, color, color * 0.7), display)
+    }
+
+    fn feature_model(&mut self, feature: Feature, display: &Display<WindowSurface>) -> Rc<RefCell<Model>> {
+        let key = format!("procedural/feature/{}", feature.name());
+        let color = feature.color();
+        self.prism_asset(&key, HexPrism::new(feature.name().to_lowercase(), FEATURE_MARKER_HEIGHT, color, color * 0.7), display)
+    }
+
+    fn prism_model(&mut self, terrain: Terrain, steps: u32, display: &Display<WindowSurface>) -> Rc<RefCell<Model>> {
+        let key = format!("procedural/prism/{}/{}", terrain.name(), steps);
+        let (top_color, side_color) = terrain.colors();
+        // Named after the class, so water tiles with rocks still get the water shader override
+        let name = terrain.class().name().to_lowercase();
+        self.prism_asset(&key, HexPrism::new(name, steps as f32 * PRISM_HEIGHT_STEP, top_color, side_color), display)
+    }
+
+    // Model registered under key, the prism is only built the first time the key is asked for
+    fn prism_asset(&mut self, key: &str, prism: HexPrism, display: &Display<WindowSurface>) -> Rc<RefCell<Model>> {
+        if let Some(model) = self.assets.model(key) {
+            return model;
+        }
+        match prism.build(display, &mut self.assets.shaders) {
+            Ok(model) => self.assets.add_model(key, model, display),
+            Err(e) => {
+                error!("Couldn't build {}: {}", key, e);
+                self.assets.missing_model(display)
+            }
+        }
+    }
+
+    fn place_smooth_terrain(&mut self, rng: &mut rand::rngs::StdRng, noise: &noise::utils::NoiseMap, width: u8, height: u8, display: &Display<WindowSurface>) {
+        let mut terrain = HeightmapTerrain::new(String::from("terrain"), width as usize, height as usize);
+        for z in 0..height {
+            for x in 0..width {
+                let noise_value = noise.get_value(x as usize, z as usize);
+                let cell_terrain = TileClass::from_noise(noise_value).variant(rng);
+                let elevation = surface_height(noise_value);
+                self.record_terrain(x, z, cell_terrain.palette_index(), elevation);
+                let (center_x, center_z) = tile_center(x, z);
+                terrain.set(x as usize, z as usize, Vector3::new(center_x, elevation, center_z), cell_terrain.colors().0);
+            }
+        }
+
+        // Registered like the prisms so it gets the material overrides and reloaded shaders. Every map
+        // replaces the previous surface under the same key
+        let model = match terrain.build(display, &mut self.assets.shaders) {
+            Ok(model) => self.assets.add_model("procedural/terrain/smooth", model, display),
+            Err(e) => {
+                error!("Couldn't build smooth terrain: {}", e);
+                self.assets.missing_model(display)
+            }
+        };
+        let id = self.scene.add(GameObject::new(String::from("Terrain")));
+        if let Some(entity) = self.scene.entity(id) {
+            self.scene.world.insert(entity, Renderable::new(model));
+        }
+    }
+}
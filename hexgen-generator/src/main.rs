@@ -3,6 +3,7 @@ use glium::Surface;
 use hexgen_core::game_loop::GameLoop;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
+use winit::event::{DeviceEvent, Event};
 use hexgen_common::vector3::Vector3;
 use hexgen_generator::Generator;
 use hexgen_generator::ui::{UI};
@@ -26,7 +27,7 @@ fn main() {
     window.set_maximized(true);
     window.set_title("Hexgen simulation");
     let perspective = Perspective::new(PI / 3.0, 1024.0, 0.1, init_frame.get_dimensions().0 as f32, init_frame.get_dimensions().1 as f32);
-    let camera = Camera::new(Vector3::new(11.0, 7.0, 1.0), Vector3::new(-3.0, -3.0, 0.0), Vector3::up(), perspective);
+    let camera = Camera::new(Vector3::new(11.0, 7.0, 1.0), Vector3::new(-3.0, -3.0, 0.0), Vector3::up(), Box::new(perspective));
     let directional_light = DirectionalLight::new(Vector3::new(-0.2, 0.4, -0.7));
     let params = glium::DrawParameters {
         depth: glium::Depth {
@@ -46,16 +47,24 @@ fn main() {
                       g.game_state.init_scene(display);
                       info!("Scene initialization finished");
                   },
-                  |_g| {
-                      return;
+                  |g| {
+                      let delta_time = g.fixed_time_step() as f32;
+                      g.game_state.update(delta_time);
                   },
                   move |g, display, egui_glium| {
+                      let delta_time = g.last_frame_time() as f32;
+                      g.game_state.reload_shaders(display);
+                      g.game_state.camera_controller.update(&mut g.game_state.renderer.camera, delta_time);
+                      g.game_state.renderer.time = g.running_time() as f32;
                       let mut frame = display.draw();
-                      g.game_state.renderer.render(&mut g.game_state.game_objects, &mut frame);
+                      g.game_state.renderer.render(&mut g.game_state.scene, &mut frame);
                       ui.redraw(&mut g.game_state, g.frame_rate, display, &g.window, egui_glium, &mut frame);
                       frame.finish().unwrap();
                   },
                   |g, e, display, control_flow| {
+                      if let Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } = e {
+                          g.game_state.camera_controller.mouse_motion(delta.0 as f32, delta.1 as f32);
+                      }
                       g.game_state.renderer.window_event_handler(e, display, control_flow);
                   },
     );
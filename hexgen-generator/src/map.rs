@@ -0,0 +1,494 @@
+use rand::Rng;
+use hexgen_common::grid::Grid;
+use hexgen_common::vector3::Vector3;
+use crate::regions::RegionMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TileClass {
+    Water,
+    Dirt,
+    Grass,
+    Stone,
+}
+
+impl TileClass {
+    // Same thresholds the stacked OBJ tiles use
+    pub fn from_noise(noise_value: f64) -> TileClass {
+        if noise_value < -0.3 {
+            TileClass::Water
+        } else if noise_value < 0.0 {
+            TileClass::Dirt
+        } else if noise_value < 0.6 {
+            TileClass::Grass
+        } else {
+            TileClass::Stone
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileClass::Water => "Water",
+            TileClass::Dirt => "Dirt",
+            TileClass::Grass => "Grass",
+            TileClass::Stone => "Stone",
+        }
+    }
+
+    // Top and side colour
+    pub fn colors(&self) -> (Vector3, Vector3) {
+        let dirt = Vector3::new(0.45, 0.32, 0.2);
+        match self {
+            TileClass::Water => (Vector3::new(0.2, 0.45, 0.65), Vector3::new(0.12, 0.3, 0.45)),
+            TileClass::Dirt => (Vector3::new(0.52, 0.38, 0.24), dirt),
+            TileClass::Grass => (Vector3::new(0.33, 0.6, 0.25), dirt),
+            TileClass::Stone => (Vector3::new(0.6, 0.6, 0.62), Vector3::new(0.42, 0.4, 0.38)),
+        }
+    }
+
+    // Terrain of a tile of this class without any feature on it
+    pub fn plain_terrain(&self) -> Terrain {
+        match self {
+            TileClass::Water => Terrain::Water,
+            TileClass::Dirt => Terrain::Dirt,
+            TileClass::Grass => Terrain::Grass,
+            TileClass::Stone => Terrain::Stone,
+        }
+    }
+
+    // Top terrain of a tile of this class, with the odds the OBJ tiles pick their top model with
+    pub fn variant<R: Rng + ?Sized>(&self, rng: &mut R) -> Terrain {
+        match self {
+            TileClass::Water => if rng.gen_range(0.0, 1.0) < 0.75 { Terrain::Water } else { Terrain::WaterRocks },
+            TileClass::Dirt => Terrain::from_palette_index(2 + rng.gen_range(0, 2)),
+            TileClass::Grass => Terrain::from_palette_index(4 + rng.gen_range(0, 3)),
+            TileClass::Stone => Terrain::from_palette_index(7 + rng.gen_range(0, 3)),
+        }
+    }
+}
+
+// Terrain of the top tile of a cell, in the order of the TERRAIN_MODELS palette
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Terrain {
+    Water,
+    WaterRocks,
+    Dirt,
+    DirtLumber,
+    Grass,
+    GrassForest,
+    GrassHill,
+    Stone,
+    StoneHill,
+    StoneMountain,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 10] = [
+        Terrain::Water,
+        Terrain::WaterRocks,
+        Terrain::Dirt,
+        Terrain::DirtLumber,
+        Terrain::Grass,
+        Terrain::GrassForest,
+        Terrain::GrassHill,
+        Terrain::Stone,
+        Terrain::StoneHill,
+        Terrain::StoneMountain,
+    ];
+
+    pub fn from_palette_index(index: usize) -> Terrain {
+        Terrain::ALL[index.min(Terrain::ALL.len() - 1)]
+    }
+
+    pub fn palette_index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn class(&self) -> TileClass {
+        match self {
+            Terrain::Water | Terrain::WaterRocks => TileClass::Water,
+            Terrain::Dirt | Terrain::DirtLumber => TileClass::Dirt,
+            Terrain::Grass | Terrain::GrassForest | Terrain::GrassHill => TileClass::Grass,
+            Terrain::Stone | Terrain::StoneHill | Terrain::StoneMountain => TileClass::Stone,
+        }
+    }
+
+    pub fn is_water(&self) -> bool {
+        self.class() == TileClass::Water
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Terrain::Water => "Water",
+            Terrain::WaterRocks => "WaterRocks",
+            Terrain::Dirt => "Dirt",
+            Terrain::DirtLumber => "DirtLumber",
+            Terrain::Grass => "Grass",
+            Terrain::GrassForest => "GrassForest",
+            Terrain::GrassHill => "GrassHill",
+            Terrain::Stone => "Stone",
+            Terrain::StoneHill => "StoneHill",
+            Terrain::StoneMountain => "StoneMountain",
+        }
+    }
+
+    // Top and side colour, variants tint the top colour of their class so procedural tiles show them
+    pub fn colors(&self) -> (Vector3, Vector3) {
+        let (top, side) = self.class().colors();
+        let top = match self {
+            Terrain::WaterRocks => Vector3::new(0.3, 0.44, 0.52),
+            Terrain::DirtLumber => Vector3::new(0.4, 0.34, 0.18),
+            Terrain::GrassForest => Vector3::new(0.18, 0.42, 0.16),
+            Terrain::GrassHill => Vector3::new(0.46, 0.6, 0.3),
+            Terrain::StoneHill => Vector3::new(0.5, 0.49, 0.48),
+            Terrain::StoneMountain => Vector3::new(0.82, 0.82, 0.86),
+            _ => top,
+        };
+        (top, side)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MapCell {
+    pub terrain: Terrain,
+    // World height of the top surface
+    pub elevation: f32,
+}
+
+// Gameplay view of a generated map, one cell per tile column
+#[derive(Clone, Debug)]
+pub struct TerrainMap {
+    cells: Grid<MapCell>,
+}
+
+impl TerrainMap {
+    pub fn new(width: u8, height: u8) -> TerrainMap {
+        TerrainMap {
+            cells: Grid::new(width, height, MapCell { terrain: Terrain::Water, elevation: 0.0 }),
+        }
+    }
+
+    pub fn width(&self) -> u8 {
+        self.cells.width()
+    }
+
+    pub fn height(&self) -> u8 {
+        self.cells.height()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        self.cells.contains(x, z)
+    }
+
+    // Cells are stored row by row, per-cell arrays of the same length can use the same index.
+    // Panics outside the map
+    pub fn index(&self, x: u8, z: u8) -> usize {
+        self.cells.index(x, z).expect("cell outside the map")
+    }
+
+    pub fn coordinates(&self, index: usize) -> (u8, u8) {
+        self.cells.coordinates(index)
+    }
+
+    pub fn get(&self, x: u8, z: u8) -> Option<&MapCell> {
+        self.cells.get(x, z)
+    }
+
+    pub fn get_mut(&mut self, x: u8, z: u8) -> Option<&mut MapCell> {
+        self.cells.get_mut(x, z)
+    }
+
+    pub fn set(&mut self, x: u8, z: u8, cell: MapCell) {
+        self.cells.set(x, z, cell);
+    }
+
+    // Neighbours inside the map, with the direction they lie in
+    pub fn neighbors(&self, x: u8, z: u8) -> impl Iterator<Item = (u8, (u8, u8))> + '_ {
+        (0..6).filter_map(move |direction| {
+            let (nx, nz) = neighbor(x as i32, z as i32, direction);
+            self.contains(nx, nz).then_some((direction, (nx as u8, nz as u8)))
+        })
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = ((u8, u8), &MapCell)> {
+        self.cells.iter()
+    }
+
+    // Land masses, water bodies, coastline and watersheds of the map as it is now
+    pub fn regions(&self) -> RegionMap {
+        RegionMap::new(self)
+    }
+}
+
+// World position of a tile centre, odd columns are shifted half a tile along X
+pub fn tile_center(x: u8, z: u8) -> (f32, f32) {
+    ((x % 2) as f32 / 2.0 + z as f32, x as f32 / 1.2)
+}
+
+// Cell whose centre is closest to a world position, the inverse of tile_center. May lie outside the map.
+// fog_of_war_cell in res/shaders/common/fog_of_war.glsl does the same on the GPU, keep the two in sync
+pub fn cell_at(world_x: f32, world_z: f32) -> (i32, i32) {
+    let column = (world_z * 1.2).round() as i32;
+    (column - 1..=column + 1).map(|x| {
+        let shift = x.rem_euclid(2) as f32 / 2.0;
+        let z = (world_x - shift).round() as i32;
+        let (dx, dz) = (world_x - shift - z as f32, world_z - x as f32 / 1.2);
+        ((x, z), dx * dx + dz * dz)
+    }).min_by(|a, b| a.1.total_cmp(&b.1)).map_or((column, 0), |(cell, _)| cell)
+}
+
+// Height of the top surface for a noise value, used by the prism and smooth styles. On land it's the
+// continuous version of the stacked tiles, which record the rounded height of their real top tile instead.
+// Water sits at the noise level
+pub fn surface_height(noise_value: f64) -> f32 {
+    if TileClass::from_noise(noise_value) == TileClass::Water {
+        0.1 + noise_value as f32 / 12.0
+    } else {
+        ((1.0 + noise_value as f32) * 3.0 + 1.0) * 0.1 + noise_value as f32 / 12.0
+    }
+}
+
+// Grid cell next to (x, z) in the given direction, direction i points at i * 60 degrees in world space.
+// Cell columns (x) run along the world Z axis and odd columns are shifted by half a tile along X.
+pub fn neighbor(x: i32, z: i32, direction: u8) -> (i32, i32) {
+    let odd = x.rem_euclid(2) == 1;
+    match direction % 6 {
+        0 => (x, z + 1),
+        1 => (x + 1, if odd { z + 1 } else { z }),
+        2 => (x + 1, if odd { z } else { z - 1 }),
+        3 => (x, z - 1),
+        4 => (x - 1, if odd { z } else { z - 1 }),
+        _ => (x - 1, if odd { z + 1 } else { z }),
+    }
+}
+
+// Cube coordinates (q, r, s) of a cell, q + r + s is always 0
+pub fn cube(x: i32, z: i32) -> (i32, i32, i32) {
+    let q = x;
+    let r = z - (x - x.rem_euclid(2)) / 2;
+    (q, r, -q - r)
+}
+
+// Number of steps between two cells
+pub fn hex_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    let (aq, ar, a_s) = cube(a.0, a.1);
+    let (bq, br, bs) = cube(b.0, b.1);
+    ((aq - bq).abs() + (ar - br).abs() + (a_s - bs).abs()) / 2
+}
+
+// hex_distance for cells inside a map
+pub fn cell_distance(a: (u8, u8), b: (u8, u8)) -> u32 {
+    hex_distance((a.0 as i32, a.1 as i32), (b.0 as i32, b.1 as i32)) as u32
+}
+
+// Inverse of cube, r is enough since s follows from q and r
+pub fn from_cube(q: i32, r: i32) -> (i32, i32) {
+    (q, r + (q - q.rem_euclid(2)) / 2)
+}
+
+// Cells on the straight line from a to b, both included. A line running exactly along a cell edge
+// always picks the same side of it, the reversed line picks the other one
+pub fn hex_line(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+    let steps = hex_distance(a, b);
+    if steps == 0 {
+        return vec![a];
+    }
+    let (aq, ar, a_s) = cube(a.0, a.1);
+    let (bq, br, bs) = cube(b.0, b.1);
+    // Nudged off the edges so ties round consistently
+    let (aq, ar, a_s) = (aq as f32 + 1e-4, ar as f32 + 2e-4, a_s as f32 - 3e-4);
+    (0..=steps).map(|step| {
+        let t = step as f32 / steps as f32;
+        let q = aq + (bq as f32 - aq) * t;
+        let r = ar + (br as f32 - ar) * t;
+        let s = a_s + (bs as f32 - a_s) * t;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        // Rounding can break q + r + s = 0, the component that moved most is derived from the other two
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        from_cube(rq as i32, rr as i32)
+    }).collect()
+}
+
+// Cells exactly radius steps away from center, walking around it. May include cells outside the map
+pub fn ring(center: (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+    if radius <= 0 {
+        return vec![center];
+    }
+    let mut cell = center;
+    for _ in 0..radius {
+        cell = neighbor(cell.0, cell.1, 4);
+    }
+    let mut cells = Vec::with_capacity(6 * radius as usize);
+    for direction in 0..6 {
+        for _ in 0..radius {
+            cells.push(cell);
+            cell = neighbor(cell.0, cell.1, direction);
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Map fixture, one string per row z and one character per column x:
+    // ~ water, r water rocks, d dirt, l dirt lumber, . grass, f grass forest, h grass hill,
+    // s stone, o stone hill, M stone mountain. Land lies one tile layer above the water
+    pub(crate) fn map_from_rows(rows: &[&str]) -> TerrainMap {
+        let mut map = TerrainMap::new(rows[0].len() as u8, rows.len() as u8);
+        for (z, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.chars().enumerate() {
+                let terrain = match symbol {
+                    '~' => Terrain::Water,
+                    'r' => Terrain::WaterRocks,
+                    'd' => Terrain::Dirt,
+                    'l' => Terrain::DirtLumber,
+                    '.' => Terrain::Grass,
+                    'f' => Terrain::GrassForest,
+                    'h' => Terrain::GrassHill,
+                    's' => Terrain::Stone,
+                    'o' => Terrain::StoneHill,
+                    'M' => Terrain::StoneMountain,
+                    _ => panic!("unknown map symbol {}", symbol),
+                };
+                let elevation = if terrain.is_water() { 0.0 } else { 0.1 };
+                map.set(x as u8, z as u8, MapCell { terrain, elevation });
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn neighbors_are_one_step_away_and_lead_back() {
+        for x in -3..6 {
+            for z in -3..6 {
+                for direction in 0..6 {
+                    let next = neighbor(x, z, direction);
+                    assert_eq!(hex_distance((x, z), next), 1);
+                    assert_eq!(neighbor(next.0, next.1, (direction + 3) % 6), (x, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distance_in_cube_coordinates() {
+        for (x, z) in [(0, 0), (3, 2), (-2, 5), (7, -1)] {
+            let (q, r, s) = cube(x, z);
+            assert_eq!(q + r + s, 0);
+            assert_eq!(hex_distance((x, z), (x, z)), 0);
+        }
+        assert_eq!(hex_distance((0, 0), (0, 5)), 5);
+        assert_eq!(hex_distance((0, 0), (4, 0)), 4);
+        // Diagonal steps along x gain half a row each
+        assert_eq!(hex_distance((0, 0), (4, 2)), 4);
+        assert_eq!(hex_distance((1, 0), (3, 3)), 4);
+        assert_eq!(hex_distance((2, 3), (5, 1)), hex_distance((5, 1), (2, 3)));
+    }
+
+    #[test]
+    fn map_cells_and_neighbours() {
+        let map = map_from_rows(&["~..", ".M.", "..~"]);
+        assert_eq!((map.width(), map.height(), map.len()), (3, 3, 9));
+        assert_eq!(map.get(1, 1).unwrap().terrain, Terrain::StoneMountain);
+        assert_eq!(map.get(3, 0), None);
+        assert!(map.contains(2, 2) && !map.contains(-1, 0) && !map.contains(0, 3));
+        for ((x, z), _) in map.cells() {
+            assert_eq!(map.coordinates(map.index(x, z)), (x, z));
+        }
+        // Corners have fewer neighbours, every one of them in the map and next to the cell
+        assert_eq!(map.neighbors(1, 1).count(), 6);
+        assert_eq!(map.neighbors(0, 0).count(), 2);
+        for (direction, (nx, nz)) in map.neighbors(0, 2) {
+            assert_eq!(neighbor(0, 2, direction), (nx as i32, nz as i32));
+        }
+    }
+
+    #[test]
+    fn rings_hold_every_cell_at_radius() {
+        for center in [(4, 4), (5, 4), (0, 0)] {
+            assert_eq!(ring(center, 0), vec![center]);
+            for radius in 1..5 {
+                let cells = ring(center, radius);
+                assert_eq!(cells.len(), 6 * radius as usize);
+                assert!(cells.iter().all(|cell| hex_distance(center, *cell) == radius));
+                let mut unique = cells.clone();
+                unique.sort();
+                unique.dedup();
+                assert_eq!(unique.len(), cells.len());
+            }
+        }
+    }
+
+    #[test]
+    fn lines_step_one_cell_at_a_time() {
+        for a in [(0, 0), (1, 3), (4, 2)] {
+            for bx in -1..7 {
+                for bz in -1..7 {
+                    let b = (bx, bz);
+                    let line = hex_line(a, b);
+                    assert_eq!(line.len(), hex_distance(a, b) as usize + 1);
+                    assert_eq!((line[0], line[line.len() - 1]), (a, b));
+                    for (step, pair) in line.windows(2).enumerate() {
+                        assert_eq!(hex_distance(pair[0], pair[1]), 1);
+                        assert_eq!(hex_distance(a, pair[1]), step as i32 + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_cube_inverts_cube() {
+        for x in -4..5 {
+            for z in -4..5 {
+                let (q, r, _) = cube(x, z);
+                assert_eq!(from_cube(q, r), (x, z));
+            }
+        }
+    }
+
+    #[test]
+    fn cell_at_inverts_tile_center() {
+        let map = TerrainMap::new(12, 9);
+        for ((x, z), _) in map.cells() {
+            let (world_x, world_z) = tile_center(x, z);
+            assert_eq!(cell_at(world_x, world_z), (x as i32, z as i32));
+            // Anywhere well inside the tile maps back to it as well
+            assert_eq!(cell_at(world_x + 0.3, world_z - 0.25), (x as i32, z as i32));
+            assert_eq!(cell_at(world_x - 0.3, world_z + 0.25), (x as i32, z as i32));
+        }
+        assert_eq!(cell_at(-1.0, 0.0), (0, -1));
+    }
+
+    #[test]
+    fn variants_stay_in_their_class() {
+        use rand::SeedableRng;
+        use std::collections::HashSet;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let classes = [TileClass::Water, TileClass::Dirt, TileClass::Grass, TileClass::Stone];
+        let mut seen = HashSet::new();
+        for _ in 0..200 {
+            for class in classes.iter() {
+                let terrain = class.variant(&mut rng);
+                assert_eq!(terrain.class(), *class);
+                seen.insert(terrain);
+            }
+        }
+        // Every variant shows up, so the feature rules on hills, forests and rocks can match procedural maps
+        assert_eq!(seen.len(), Terrain::ALL.len());
+    }
+}
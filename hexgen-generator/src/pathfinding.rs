@@ -0,0 +1,336 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use hexgen_common::grid::Grid;
+use crate::map::{hex_distance, neighbor, Terrain, TerrainMap};
+
+// Cost of entering a cell, per terrain of the cell entered plus a charge for every world unit climbed or descended.
+// None marks terrain that can't be entered at all
+#[derive(Clone, Debug)]
+pub struct MovementCosts {
+    pub terrain: HashMap<Terrain, Option<f32>>,
+    pub climb: f32,
+    pub descent: f32,
+    // Steps climbing more than this are impassable, a cliff
+    pub max_climb: Option<f32>,
+}
+
+impl Default for MovementCosts {
+    fn default() -> Self {
+        let terrain = Terrain::ALL.iter().map(|terrain| (*terrain, match terrain {
+            Terrain::Water | Terrain::WaterRocks => None,
+            Terrain::Dirt | Terrain::Grass => Some(1.0),
+            Terrain::DirtLumber | Terrain::Stone => Some(1.5),
+            Terrain::GrassForest | Terrain::GrassHill => Some(2.0),
+            Terrain::StoneHill => Some(3.0),
+            Terrain::StoneMountain => Some(6.0),
+        })).collect();
+
+        MovementCosts {
+            terrain,
+            // A stacked tile layer is 0.1 high, climbing one costs as much as a plain step
+            climb: 10.0,
+            descent: 0.0,
+            max_climb: None,
+        }
+    }
+}
+
+impl MovementCosts {
+    pub fn set(&mut self, terrain: Terrain, cost: Option<f32>) {
+        self.terrain.insert(terrain, cost);
+    }
+
+    pub fn terrain_cost(&self, terrain: Terrain) -> Option<f32> {
+        self.terrain.get(&terrain).copied().flatten()
+    }
+
+    // Cost of moving between two neighbouring cells, None when to can't be entered from from
+    pub fn step_cost(&self, map: &TerrainMap, from: (u8, u8), to: (u8, u8)) -> Option<f32> {
+        let from = map.get(from.0, from.1)?;
+        let to = map.get(to.0, to.1)?;
+        let base = self.terrain_cost(to.terrain)?;
+        let rise = to.elevation - from.elevation;
+        if self.max_climb.is_some_and(|max_climb| rise > max_climb) {
+            return None;
+        }
+        let slope = if rise > 0.0 { rise * self.climb } else { -rise * self.descent };
+        Some(base + slope)
+    }
+
+    // Never overestimates a step, keeps the A* heuristic admissible
+    fn cheapest_step(&self) -> f32 {
+        self.terrain.values().flatten().copied().fold(f32::INFINITY, f32::min).max(0.0)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Path {
+    // Start and goal included
+    pub cells: Vec<(u8, u8)>,
+    pub cost: f32,
+}
+
+// Min-heap entry, BinaryHeap is a max-heap so the ordering is reversed
+#[derive(Copy, Clone, PartialEq)]
+struct Open {
+    priority: f32,
+    index: usize,
+}
+
+impl Eq for Open {}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority).then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Cheapest path from start to goal, None when the goal can't be reached
+pub fn find_path(map: &TerrainMap, costs: &MovementCosts, start: (u8, u8), goal: (u8, u8)) -> Option<Path> {
+    map.get(start.0, start.1)?;
+    map.get(goal.0, goal.1)?;
+    let heuristic_step = costs.cheapest_step();
+    let heuristic = |cell: (u8, u8)| {
+        hex_distance((cell.0 as i32, cell.1 as i32), (goal.0 as i32, goal.1 as i32)) as f32 * heuristic_step
+    };
+
+    let mut best = vec![f32::INFINITY; map.len()];
+    let mut came_from: Vec<Option<usize>> = vec![None; map.len()];
+    let mut open = BinaryHeap::new();
+    let start_index = map.index(start.0, start.1);
+    let goal_index = map.index(goal.0, goal.1);
+    best[start_index] = 0.0;
+    open.push(Open { priority: heuristic(start), index: start_index });
+
+    while let Some(Open { priority, index }) = open.pop() {
+        let cell = map.coordinates(index);
+        if index == goal_index {
+            return Some(Path { cells: trace_back(map, &came_from, index), cost: best[index] });
+        }
+        // Stale entry left behind after a cheaper route to the cell was found
+        if priority > best[index] + heuristic(cell) {
+            continue;
+        }
+        for (_, next) in map.neighbors(cell.0, cell.1) {
+            let step = match costs.step_cost(map, cell, next) {
+                Some(step) => step,
+                None => continue,
+            };
+            let next_index = map.index(next.0, next.1);
+            let cost = best[index] + step;
+            if cost < best[next_index] {
+                best[next_index] = cost;
+                came_from[next_index] = Some(index);
+                open.push(Open { priority: cost + heuristic(next), index: next_index });
+            }
+        }
+    }
+    None
+}
+
+fn trace_back(map: &TerrainMap, came_from: &[Option<usize>], goal: usize) -> Vec<(u8, u8)> {
+    let mut cells = vec![map.coordinates(goal)];
+    let mut current = goal;
+    while let Some(previous) = came_from[current] {
+        cells.push(map.coordinates(previous));
+        current = previous;
+    }
+    cells.reverse();
+    cells
+}
+
+// Every cell reachable from start spending at most budget, with the cheapest cost to get there
+pub fn reachable(map: &TerrainMap, costs: &MovementCosts, start: (u8, u8), budget: f32) -> HashMap<(u8, u8), f32> {
+    let mut result = HashMap::new();
+    if map.get(start.0, start.1).is_none() {
+        return result;
+    }
+    let mut best = vec![f32::INFINITY; map.len()];
+    let mut open = BinaryHeap::new();
+    let start_index = map.index(start.0, start.1);
+    best[start_index] = 0.0;
+    open.push(Open { priority: 0.0, index: start_index });
+
+    while let Some(Open { priority, index }) = open.pop() {
+        if priority > best[index] {
+            continue;
+        }
+        let cell = map.coordinates(index);
+        result.insert(cell, priority);
+        for (_, next) in map.neighbors(cell.0, cell.1) {
+            let step = match costs.step_cost(map, cell, next) {
+                Some(step) => step,
+                None => continue,
+            };
+            let next_index = map.index(next.0, next.1);
+            let cost = priority + step;
+            if cost <= budget && cost < best[next_index] {
+                best[next_index] = cost;
+                open.push(Open { priority: cost, index: next_index });
+            }
+        }
+    }
+    result
+}
+
+// For every cell, the direction of the next step on the cheapest way to the nearest goal.
+// Built once per goal set, any number of units can then follow it without their own searches
+pub struct FlowField {
+    costs: Grid<f32>,
+    directions: Grid<Option<u8>>,
+}
+
+impl FlowField {
+    pub fn new(map: &TerrainMap, costs: &MovementCosts, goals: &[(u8, u8)]) -> FlowField {
+        let mut field = FlowField {
+            costs: Grid::new(map.width(), map.height(), f32::INFINITY),
+            directions: Grid::new(map.width(), map.height(), None),
+        };
+        let mut open = BinaryHeap::new();
+        for goal in goals {
+            if map.get(goal.0, goal.1).is_some() {
+                let index = map.index(goal.0, goal.1);
+                field.costs[index] = 0.0;
+                open.push(Open { priority: 0.0, index });
+            }
+        }
+
+        // Dijkstra outwards from the goals over reversed edges, the cost of stepping from the neighbour into the cell
+        while let Some(Open { priority, index }) = open.pop() {
+            if priority > field.costs[index] {
+                continue;
+            }
+            let cell = map.coordinates(index);
+            for (direction, previous) in map.neighbors(cell.0, cell.1) {
+                let step = match costs.step_cost(map, previous, cell) {
+                    Some(step) => step,
+                    None => continue,
+                };
+                let previous_index = map.index(previous.0, previous.1);
+                let cost = priority + step;
+                if cost < field.costs[previous_index] {
+                    field.costs[previous_index] = cost;
+                    // The neighbour walks back the way we came, the opposite direction
+                    field.directions[previous_index] = Some((direction + 3) % 6);
+                    open.push(Open { priority: cost, index: previous_index });
+                }
+            }
+        }
+        field
+    }
+
+    // Remaining cost to the nearest goal, None when no goal can be reached
+    pub fn cost(&self, x: u8, z: u8) -> Option<f32> {
+        self.costs.get(x, z).copied().filter(|cost| cost.is_finite())
+    }
+
+    // Neighbour direction to step in, None at a goal or where no goal can be reached
+    pub fn direction(&self, x: u8, z: u8) -> Option<u8> {
+        *self.directions.get(x, z)?
+    }
+
+    pub fn next(&self, x: u8, z: u8) -> Option<(u8, u8)> {
+        let direction = self.direction(x, z)?;
+        let (nx, nz) = neighbor(x as i32, z as i32, direction);
+        Some((nx as u8, nz as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::tests::map_from_rows;
+
+    #[test]
+    fn water_is_impassable() {
+        let map = map_from_rows(&[
+            "..~..",
+            "..~..",
+            "..~..",
+        ]);
+        let costs = MovementCosts::default();
+        assert_eq!(find_path(&map, &costs, (0, 1), (4, 1)), None);
+        assert_eq!(find_path(&map, &costs, (0, 1), (2, 1)), None);
+        assert!(reachable(&map, &costs, (0, 1), 100.0).keys().all(|(x, _)| *x < 2));
+        assert_eq!(FlowField::new(&map, &costs, &[(4, 1)]).cost(0, 1), None);
+    }
+
+    #[test]
+    fn path_detours_around_mountain() {
+        let map = map_from_rows(&[
+            ".....",
+            ".....",
+            "..M..",
+            ".....",
+            ".....",
+        ]);
+        let costs = MovementCosts::default();
+        // Straight down column 2 would cross the mountain for 6, going round it costs one extra step
+        let path = find_path(&map, &costs, (2, 0), (2, 4)).unwrap();
+        assert!(!path.cells.contains(&(2, 2)));
+        assert_eq!(path.cost, 5.0);
+        assert_eq!(path.cells.len(), 6);
+        assert_eq!((path.cells[0], path.cells[5]), ((2, 0), (2, 4)));
+        for step in path.cells.windows(2) {
+            assert_eq!(hex_distance((step[0].0 as i32, step[0].1 as i32), (step[1].0 as i32, step[1].1 as i32)), 1);
+        }
+
+        // Climbing onto a hill one layer up costs its terrain plus a plain step
+        let mut hill = map.clone();
+        hill.set(2, 2, crate::map::MapCell { terrain: Terrain::GrassHill, elevation: 0.2 });
+        assert_eq!(costs.step_cost(&hill, (2, 1), (2, 2)), Some(3.0));
+        assert_eq!(costs.step_cost(&hill, (2, 2), (2, 3)), Some(1.0));
+    }
+
+    #[test]
+    fn reachable_stays_within_budget() {
+        let map = map_from_rows(&[
+            "..f..",
+            "..f..",
+            ".....",
+        ]);
+        let costs = MovementCosts::default();
+        let reached = reachable(&map, &costs, (0, 0), 3.0);
+        assert_eq!(reached[&(0, 0)], 0.0);
+        assert!(reached.values().all(|cost| *cost <= 3.0));
+        // Every cell is there exactly when its cheapest path fits the budget, at that path's cost
+        for ((x, z), _) in map.cells() {
+            let cheapest = find_path(&map, &costs, (0, 0), (x, z)).map(|path| path.cost);
+            assert_eq!(reached.get(&(x, z)).copied(), cheapest.filter(|cost| *cost <= 3.0));
+        }
+    }
+
+    #[test]
+    fn flow_field_leads_to_goal() {
+        let map = map_from_rows(&[
+            "......",
+            ".MMMM.",
+            "...f..",
+            "~~..~~",
+        ]);
+        let costs = MovementCosts::default();
+        let goal = (2, 0);
+        let field = FlowField::new(&map, &costs, &[goal]);
+        for ((x, z), _) in map.cells() {
+            let mut cell = (x, z);
+            let mut spent = 0.0;
+            while let Some(next) = field.next(cell.0, cell.1) {
+                spent += costs.step_cost(&map, cell, next).unwrap();
+                cell = next;
+                assert!(spent <= 100.0, "the flow from {},{} loops", x, z);
+            }
+            assert_eq!(cell, goal);
+            // Following the field costs what it promised, the same as the cheapest path
+            assert_eq!(Some(spent), field.cost(x, z));
+            if !map.get(x, z).unwrap().terrain.is_water() {
+                assert_eq!(find_path(&map, &costs, (x, z), goal).map(|path| path.cost), field.cost(x, z));
+            }
+        }
+    }
+}
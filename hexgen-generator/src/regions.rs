@@ -0,0 +1,364 @@
+use std::collections::{HashMap, VecDeque};
+use hexgen_common::grid::Grid;
+use crate::map::{neighbor, tile_center, TerrainMap};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RegionKind {
+    // A connected land mass, continent or island
+    Land,
+    // Water touching the map edge
+    Ocean,
+    // Water enclosed by land
+    Lake,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RegionStats {
+    // In cells
+    pub area: usize,
+    // Mean of the tile centres, world X and Z
+    pub centroid: (f32, f32),
+    // Bounding box in cell coordinates, both corners included
+    pub min: (u8, u8),
+    pub max: (u8, u8),
+}
+
+impl RegionStats {
+    fn from_cells(cells: &[(u8, u8)]) -> RegionStats {
+        let mut sum = (0.0, 0.0);
+        let mut min = (u8::MAX, u8::MAX);
+        let mut max = (0, 0);
+        for &(x, z) in cells {
+            let center = tile_center(x, z);
+            sum = (sum.0 + center.0, sum.1 + center.1);
+            min = (min.0.min(x), min.1.min(z));
+            max = (max.0.max(x), max.1.max(z));
+        }
+        let area = cells.len();
+        RegionStats {
+            area,
+            centroid: (sum.0 / area.max(1) as f32, sum.1 / area.max(1) as f32),
+            min,
+            max,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub stats: RegionStats,
+}
+
+// Land draining through the same mouth, following the steepest descent from cell to cell
+#[derive(Clone, Debug)]
+pub struct Watershed {
+    // Last land cell before the water, or the lowest cell of a basin without an outflow
+    pub mouth: (u8, u8),
+    // Water region the watershed drains into, None for closed basins
+    pub drains_into: Option<usize>,
+    pub stats: RegionStats,
+}
+
+// Structure of a generated map: land masses and water bodies, the coastline and watersheds.
+// Computed once from a TerrainMap, it goes stale when the map's terrain changes
+pub struct RegionMap {
+    labels: Grid<usize>,
+    regions: Vec<Region>,
+    coastline: Vec<(u8, u8)>,
+    coast_distance: Grid<Option<u32>>,
+    downstream: Grid<Option<u8>>,
+    watershed_labels: Grid<Option<usize>>,
+    watersheds: Vec<Watershed>,
+}
+
+impl RegionMap {
+    pub fn new(map: &TerrainMap) -> RegionMap {
+        let mut regions = RegionMap {
+            labels: Grid::new(map.width(), map.height(), usize::MAX),
+            regions: vec![],
+            coastline: vec![],
+            coast_distance: Grid::new(map.width(), map.height(), None),
+            downstream: Grid::new(map.width(), map.height(), None),
+            watershed_labels: Grid::new(map.width(), map.height(), None),
+            watersheds: vec![],
+        };
+        regions.label_regions(map);
+        regions.find_coast(map);
+        regions.find_watersheds(map);
+        regions
+    }
+
+    // Flood fills land and water separately, water reaching the map edge is ocean
+    fn label_regions(&mut self, map: &TerrainMap) {
+        for start in 0..map.len() {
+            if self.labels[start] != usize::MAX {
+                continue;
+            }
+            let id = self.regions.len();
+            let start_cell = map.coordinates(start);
+            let water = map.get(start_cell.0, start_cell.1).unwrap().terrain.is_water();
+            let mut cells = vec![];
+            let mut on_edge = false;
+            let mut queue = VecDeque::from([start_cell]);
+            self.labels[start] = id;
+            while let Some((x, z)) = queue.pop_front() {
+                cells.push((x, z));
+                on_edge |= x == 0 || z == 0 || x + 1 == map.width() || z + 1 == map.height();
+                for (_, (nx, nz)) in map.neighbors(x, z) {
+                    let index = map.index(nx, nz);
+                    if self.labels[index] == usize::MAX && map.get(nx, nz).unwrap().terrain.is_water() == water {
+                        self.labels[index] = id;
+                        queue.push_back((nx, nz));
+                    }
+                }
+            }
+
+            let kind = match (water, on_edge) {
+                (false, _) => RegionKind::Land,
+                (true, true) => RegionKind::Ocean,
+                (true, false) => RegionKind::Lake,
+            };
+            self.regions.push(Region { kind, stats: RegionStats::from_cells(&cells) });
+        }
+    }
+
+    // Coast cells are land next to water, distances are in steps over land and water alike
+    fn find_coast(&mut self, map: &TerrainMap) {
+        let mut queue = VecDeque::new();
+        for ((x, z), cell) in map.cells() {
+            if !cell.terrain.is_water() && map.neighbors(x, z).any(|(_, (nx, nz))| map.get(nx, nz).unwrap().terrain.is_water()) {
+                self.coastline.push((x, z));
+                self.coast_distance[map.index(x, z)] = Some(0);
+                queue.push_back((x, z));
+            }
+        }
+        while let Some((x, z)) = queue.pop_front() {
+            let distance = self.coast_distance[map.index(x, z)].unwrap() + 1;
+            for (_, (nx, nz)) in map.neighbors(x, z) {
+                let index = map.index(nx, nz);
+                if self.coast_distance[index].is_none() {
+                    self.coast_distance[index] = Some(distance);
+                    queue.push_back((nx, nz));
+                }
+            }
+        }
+    }
+
+    // Every land cell drains to its lowest neighbour when that is lower, or into adjacent water.
+    // Following the flow ends at a mouth, the cells sharing one form a watershed
+    fn find_watersheds(&mut self, map: &TerrainMap) {
+        for ((x, z), cell) in map.cells() {
+            if cell.terrain.is_water() {
+                continue;
+            }
+            let mut lowest: Option<(u8, f32, bool)> = None;
+            for (direction, (nx, nz)) in map.neighbors(x, z) {
+                let next = map.get(nx, nz).unwrap();
+                let water = next.terrain.is_water();
+                // Any water beats any land, among equals the lower one wins
+                let better = lowest.is_none_or(|(_, elevation, lowest_water)| {
+                    (water && !lowest_water) || (water == lowest_water && next.elevation < elevation)
+                });
+                if better {
+                    lowest = Some((direction, next.elevation, water));
+                }
+            }
+            self.downstream[map.index(x, z)] = lowest
+                .filter(|(_, elevation, water)| *water || *elevation < cell.elevation)
+                .map(|(direction, _, _)| direction);
+        }
+
+        // Watershed ids in order of the first cell reaching each mouth, with the mouth's outflow
+        let mut mouths: HashMap<(u8, u8), usize> = HashMap::new();
+        let mut outlets: Vec<((u8, u8), Option<usize>)> = vec![];
+        let mut members: Vec<Vec<(u8, u8)>> = vec![];
+        for ((x, z), cell) in map.cells() {
+            if cell.terrain.is_water() {
+                continue;
+            }
+            // Elevation strictly drops along the flow, so it always ends
+            let mut mouth = (x, z);
+            let mut outflow = None;
+            while let Some(next) = self.downstream_of(mouth) {
+                if map.get(next.0, next.1).unwrap().terrain.is_water() {
+                    outflow = Some(self.labels[map.index(next.0, next.1)]);
+                    break;
+                }
+                mouth = next;
+            }
+            let id = *mouths.entry(mouth).or_insert_with(|| {
+                outlets.push((mouth, outflow));
+                members.push(vec![]);
+                members.len() - 1
+            });
+            members[id].push((x, z));
+            self.watershed_labels[map.index(x, z)] = Some(id);
+        }
+        self.watersheds = outlets.into_iter().zip(members.iter())
+            .map(|((mouth, drains_into), cells)| Watershed { mouth, drains_into, stats: RegionStats::from_cells(cells) })
+            .collect();
+    }
+
+    pub fn width(&self) -> u8 {
+        self.labels.width()
+    }
+
+    pub fn height(&self) -> u8 {
+        self.labels.height()
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn region(&self, id: usize) -> Option<&Region> {
+        self.regions.get(id)
+    }
+
+    // Region the cell belongs to, every cell of the map belongs to exactly one
+    pub fn region_id(&self, x: u8, z: u8) -> Option<usize> {
+        self.labels.get(x, z).copied()
+    }
+
+    pub fn region_at(&self, x: u8, z: u8) -> Option<&Region> {
+        self.region_id(x, z).and_then(|id| self.regions.get(id))
+    }
+
+    // Regions of one kind together with their ids
+    pub fn regions_of(&self, kind: RegionKind) -> impl Iterator<Item = (usize, &Region)> {
+        self.regions.iter().enumerate().filter(move |(_, region)| region.kind == kind)
+    }
+
+    // Land cells next to water
+    pub fn coastline(&self) -> &[(u8, u8)] {
+        &self.coastline
+    }
+
+    pub fn is_coast(&self, x: u8, z: u8) -> bool {
+        self.coast_distance(x, z) == Some(0)
+    }
+
+    // Steps to the nearest coast cell, 0 on the coast itself. None when the map has no coast at all
+    pub fn coast_distance(&self, x: u8, z: u8) -> Option<u32> {
+        self.coast_distance.get(x, z).copied().flatten()
+    }
+
+    // Direction water flows out of a land cell, None for water and the bottoms of closed basins
+    pub fn downstream(&self, x: u8, z: u8) -> Option<u8> {
+        self.downstream.get(x, z).copied().flatten()
+    }
+
+    pub fn downstream_of(&self, cell: (u8, u8)) -> Option<(u8, u8)> {
+        let direction = self.downstream(cell.0, cell.1)?;
+        let (nx, nz) = neighbor(cell.0 as i32, cell.1 as i32, direction);
+        Some((nx as u8, nz as u8))
+    }
+
+    pub fn watersheds(&self) -> &[Watershed] {
+        &self.watersheds
+    }
+
+    // Watershed of a land cell, water cells have none
+    pub fn watershed_id(&self, x: u8, z: u8) -> Option<usize> {
+        self.watershed_labels.get(x, z).copied().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::tests::map_from_rows;
+    use crate::map::hex_distance;
+
+    // Land ringed by ocean with a one cell lake in the middle, rising towards the east.
+    // A dip west of the lake has no outflow
+    fn island() -> TerrainMap {
+        let mut map = map_from_rows(&[
+            "~~~~~~~~~",
+            "~.......~",
+            "~.......~",
+            "~...~...~",
+            "~.......~",
+            "~.......~",
+            "~~~~~~~~~",
+        ]);
+        for ((x, z), cell) in map.clone().cells() {
+            if !cell.terrain.is_water() {
+                map.get_mut(x, z).unwrap().elevation = 0.1 + 0.05 * x as f32;
+            }
+        }
+        map.get_mut(2, 3).unwrap().elevation = 0.0;
+        map
+    }
+
+    #[test]
+    fn land_ocean_and_lake() {
+        let map = island();
+        let regions = map.regions();
+        assert_eq!(regions.regions().len(), 3);
+
+        let kind_at = |x, z| regions.region_at(x, z).unwrap().kind;
+        assert_eq!(kind_at(0, 0), RegionKind::Ocean);
+        assert_eq!(kind_at(1, 1), RegionKind::Land);
+        assert_eq!(kind_at(4, 3), RegionKind::Lake);
+
+        let (_, land) = regions.regions_of(RegionKind::Land).next().unwrap();
+        assert_eq!((land.stats.area, land.stats.min, land.stats.max), (34, (1, 1), (7, 5)));
+        let (_, ocean) = regions.regions_of(RegionKind::Ocean).next().unwrap();
+        assert_eq!((ocean.stats.area, ocean.stats.min, ocean.stats.max), (28, (0, 0), (8, 6)));
+        let (_, lake) = regions.regions_of(RegionKind::Lake).next().unwrap();
+        assert_eq!((lake.stats.area, lake.stats.min, lake.stats.max), (1, (4, 3), (4, 3)));
+        assert_eq!(lake.stats.centroid, tile_center(4, 3));
+    }
+
+    #[test]
+    fn coast_distances() {
+        let map = island();
+        let regions = map.regions();
+        // The shore of the lake is coast as much as the shore of the ocean
+        assert!(regions.is_coast(1, 1) && regions.is_coast(4, 2) && regions.is_coast(3, 3));
+        assert!(!regions.is_coast(2, 3) && !regions.is_coast(0, 0));
+        for ((x, z), _) in map.cells() {
+            let closest = regions.coastline().iter()
+                .map(|coast| hex_distance((x as i32, z as i32), (coast.0 as i32, coast.1 as i32)) as u32)
+                .min();
+            assert_eq!(regions.coast_distance(x, z), closest);
+        }
+    }
+
+    #[test]
+    fn downstream_ends_at_watershed_mouth() {
+        let map = island();
+        let regions = map.regions();
+        let lake = regions.region_id(4, 3).unwrap();
+        let ocean = regions.region_id(0, 0).unwrap();
+
+        for ((x, z), cell) in map.cells() {
+            if cell.terrain.is_water() {
+                assert_eq!(regions.watershed_id(x, z), None);
+                assert_eq!(regions.downstream(x, z), None);
+                continue;
+            }
+            let mut mouth = (x, z);
+            let mut drains_into = None;
+            while let Some(next) = regions.downstream_of(mouth) {
+                if map.get(next.0, next.1).unwrap().terrain.is_water() {
+                    drains_into = regions.region_id(next.0, next.1);
+                    break;
+                }
+                assert!(map.get(next.0, next.1).unwrap().elevation < map.get(mouth.0, mouth.1).unwrap().elevation);
+                mouth = next;
+            }
+            let watershed = &regions.watersheds()[regions.watershed_id(x, z).unwrap()];
+            assert_eq!(watershed.mouth, mouth);
+            assert_eq!(watershed.drains_into, drains_into);
+        }
+
+        let drains = |target: Option<usize>| regions.watersheds().iter().filter(|watershed| watershed.drains_into == target).count();
+        assert!(drains(Some(ocean)) > 0 && drains(Some(lake)) > 0);
+        let basin = &regions.watersheds()[regions.watershed_id(2, 3).unwrap()];
+        assert_eq!((basin.mouth, basin.drains_into), ((2, 3), None));
+        assert_eq!(regions.watersheds().iter().map(|watershed| watershed.stats.area).sum::<usize>(), 34);
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::{HashMap, VecDeque};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::features::{balance_starts, place_features, Feature, FeatureMap, PlacementRule};
+use crate::map::{cell_distance, Terrain, TerrainMap};
+use crate::pathfinding::{FlowField, MovementCosts};
+
+// Starts taken from the farthest candidates are picked at random among this many
+const FARTHEST_CHOICES: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct StartSettings {
+    pub players: usize,
+    // Cells around a start counted towards its value
+    pub radius: u32,
+    // Fewest steps between two starts
+    pub min_distance: u32,
+    // Random start sets tried, the fairest one is kept
+    pub attempts: usize,
+    // Value of a cell of each terrain and of each feature on it
+    pub terrain_values: HashMap<Terrain, f32>,
+    pub feature_values: HashMap<Feature, f32>,
+    // Decide which cells can reach each other and how far apart starts are
+    pub costs: MovementCosts,
+}
+
+impl Default for StartSettings {
+    fn default() -> Self {
+        let terrain_values = Terrain::ALL.iter().map(|terrain| (*terrain, match terrain {
+            Terrain::Water | Terrain::Stone => 0.5,
+            Terrain::WaterRocks | Terrain::Dirt | Terrain::GrassHill | Terrain::StoneHill => 1.0,
+            Terrain::DirtLumber | Terrain::Grass | Terrain::GrassForest => 1.5,
+            Terrain::StoneMountain => 0.0,
+        })).collect();
+        let feature_values = Feature::ALL.iter().map(|feature| (*feature, match feature {
+            Feature::Ore => 3.0,
+            Feature::Lumber | Feature::Fish => 2.0,
+            Feature::Ruins | Feature::Cave => 1.0,
+        })).collect();
+
+        StartSettings {
+            players: 4,
+            radius: 3,
+            min_distance: 4,
+            attempts: 64,
+            terrain_values,
+            feature_values,
+            costs: MovementCosts::default(),
+        }
+    }
+}
+
+impl StartSettings {
+    // Sum of the terrain and feature values of every cell within radius
+    pub fn value(&self, map: &TerrainMap, features: &FeatureMap, start: (u8, u8)) -> f32 {
+        map.cells()
+            .filter(|(cell, _)| cell_distance(start, *cell) <= self.radius)
+            .map(|((x, z), cell)| {
+                let terrain = self.terrain_values.get(&cell.terrain).copied().unwrap_or(0.0);
+                let feature = features.get(x, z).and_then(|feature| self.feature_values.get(&feature)).copied().unwrap_or(0.0);
+                terrain + feature
+            })
+            .sum()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StartPlacement {
+    pub starts: Vec<(u8, u8)>,
+    // Terrain value around each start
+    pub values: Vec<f32>,
+    // Path cost from each start to the closest other start
+    pub nearest: Vec<f32>,
+    // 1 when all starts have the same value and the same distance to their closest neighbour, lower the less even
+    pub fairness: f32,
+}
+
+// Drawn from the map seed, so features and starts can be placed again without regenerating the terrain
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MapSeeds {
+    pub features: u64,
+    pub starts: u64,
+    // Bumped by every re-roll of the starts
+    pub roll: u64,
+}
+
+impl MapSeeds {
+    pub fn new<R: Rng + ?Sized>(rng: &mut R) -> MapSeeds {
+        MapSeeds {
+            features: rng.gen(),
+            starts: rng.gen(),
+            roll: 0,
+        }
+    }
+
+    // Only the starts change, the features are placed from the same seed again
+    pub fn reroll(&mut self) {
+        self.roll += 1;
+    }
+
+    pub fn feature_rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.features)
+    }
+
+    pub fn start_rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.starts.wrapping_add(self.roll))
+    }
+}
+
+// Places the features and the starts, then evens out the resources around the starts
+pub fn populate(map: &TerrainMap, rules: &[PlacementRule], settings: &StartSettings, seeds: &MapSeeds) -> (FeatureMap, Option<StartPlacement>) {
+    let mut features = place_features(map, rules, &mut seeds.feature_rng());
+    let mut start_rng = seeds.start_rng();
+    let starts = find_starts(map, &features, settings, &mut start_rng).map(|placement| {
+        balance_starts(map, rules, &mut features, &placement.starts, settings.radius, &mut start_rng);
+        // Balancing moved resources around, the values have to be taken again
+        evaluate_starts(map, &features, settings, placement.starts)
+    });
+    (features, starts)
+}
+
+// Land starts that can all reach each other, spread out as evenly as possible. Of several random
+// farthest-point sets the fairest is kept, so the same rng state always gives the same starts.
+// None when the largest connected land area can't fit the players
+pub fn find_starts<R: Rng + ?Sized>(map: &TerrainMap, features: &FeatureMap, settings: &StartSettings, rng: &mut R) -> Option<StartPlacement> {
+    if settings.players == 0 {
+        return None;
+    }
+    let candidates = largest_land_area(map, &settings.costs);
+    if candidates.len() < settings.players {
+        return None;
+    }
+
+    let mut best: Option<StartPlacement> = None;
+    for _ in 0..settings.attempts.max(1) {
+        let starts = match spread_starts(&candidates, settings, rng) {
+            Some(starts) => starts,
+            None => continue,
+        };
+        let placement = evaluate_starts(map, features, settings, starts);
+        if best.as_ref().is_none_or(|best| placement.fairness > best.fairness) {
+            best = Some(placement);
+        }
+    }
+    best
+}
+
+// Scores a given set of starts, also used to rescore them once resources were balanced
+pub fn evaluate_starts(map: &TerrainMap, features: &FeatureMap, settings: &StartSettings, starts: Vec<(u8, u8)>) -> StartPlacement {
+    let values: Vec<f32> = starts.iter().map(|start| settings.value(map, features, *start)).collect();
+    let fields: Vec<FlowField> = starts.iter().map(|start| FlowField::new(map, &settings.costs, &[*start])).collect();
+    let nearest: Vec<f32> = starts.iter().enumerate().map(|(index, start)| {
+        fields.iter().enumerate()
+            .filter(|(other, _)| *other != index)
+            .filter_map(|(_, field)| field.cost(start.0, start.1))
+            .fold(f32::INFINITY, f32::min)
+    }).map(|cost| if cost.is_finite() { cost } else { 0.0 }).collect();
+
+    let fairness = 1.0 - (spread(&values) + spread(&nearest)) / 2.0;
+    StartPlacement {
+        starts,
+        values,
+        nearest,
+        fairness,
+    }
+}
+
+// Relative difference between the largest and the smallest value, 0 when all are equal
+fn spread(values: &[f32]) -> f32 {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+    if values.is_empty() || max <= 0.0 {
+        return 0.0;
+    }
+    ((max - min) / max).clamp(0.0, 1.0)
+}
+
+// Random first start, then each further start among the candidates farthest from those already picked
+fn spread_starts<R: Rng + ?Sized>(candidates: &[(u8, u8)], settings: &StartSettings, rng: &mut R) -> Option<Vec<(u8, u8)>> {
+    let mut starts = vec![candidates[rng.gen_range(0, candidates.len())]];
+    // Steps from each candidate to the closest start picked so far
+    let mut nearest: Vec<u32> = candidates.iter().map(|cell| cell_distance(starts[0], *cell)).collect();
+    while starts.len() < settings.players {
+        let mut farthest: Vec<(u32, (u8, u8))> = nearest.iter().zip(candidates.iter())
+            .filter(|(distance, _)| **distance >= settings.min_distance.max(1))
+            .map(|(distance, cell)| (*distance, *cell))
+            .collect();
+        if farthest.is_empty() {
+            return None;
+        }
+        farthest.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let choices = farthest.len().min(FARTHEST_CHOICES);
+        let start = farthest[rng.gen_range(0, choices)].1;
+        for (distance, cell) in nearest.iter_mut().zip(candidates.iter()) {
+            *distance = (*distance).min(cell_distance(start, *cell));
+        }
+        starts.push(start);
+    }
+    Some(starts)
+}
+
+// Dry cells that can be entered, grouped by which can reach each other, the largest group
+fn largest_land_area(map: &TerrainMap, costs: &MovementCosts) -> Vec<(u8, u8)> {
+    let is_land = |cell: (u8, u8)| {
+        map.get(cell.0, cell.1).is_some_and(|cell| !cell.terrain.is_water() && costs.terrain_cost(cell.terrain).is_some())
+    };
+    let mut visited = vec![false; map.len()];
+    let mut largest = vec![];
+    for (start, _) in map.cells() {
+        if visited[map.index(start.0, start.1)] || !is_land(start) {
+            continue;
+        }
+        let mut area = vec![];
+        let mut queue = VecDeque::from([start]);
+        visited[map.index(start.0, start.1)] = true;
+        while let Some(cell) = queue.pop_front() {
+            area.push(cell);
+            for (_, next) in map.neighbors(cell.0, cell.1) {
+                let index = map.index(next.0, next.1);
+                if !visited[index] && is_land(next) && costs.step_cost(map, cell, next).is_some() {
+                    visited[index] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        if area.len() > largest.len() {
+            largest = area;
+        }
+    }
+    largest
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::tests::map_from_rows;
+    use super::*;
+
+    // A large island on the left and a small one on the right, split by open water
+    fn two_islands() -> TerrainMap {
+        map_from_rows(&[
+            "~~~~~~~~~~~~~~",
+            "~..f..h..~~~~~",
+            "~.d..l...~~~~~",
+            "~..h..f.d~~..~",
+            "~.f..s..o~~..~",
+            "~..d..h..~~..~",
+            "~.l..f...~~~~~",
+            "~...o..d.~~~~~",
+            "~~~~~~~~~~~~~~",
+        ])
+    }
+
+    fn settings() -> StartSettings {
+        StartSettings {
+            players: 3,
+            min_distance: 3,
+            attempts: 16,
+            ..StartSettings::default()
+        }
+    }
+
+    #[test]
+    fn starts_are_spread_over_the_largest_island() {
+        let map = two_islands();
+        let features = FeatureMap::new(map.width(), map.height());
+        let settings = settings();
+        for seed in 0..8 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let placement = find_starts(&map, &features, &settings, &mut rng).expect("the large island fits three players");
+            assert_eq!(placement.starts.len(), settings.players);
+            for (index, start) in placement.starts.iter().enumerate() {
+                assert!(!map.get(start.0, start.1).unwrap().terrain.is_water());
+                // The small island is never picked, it can't reach the others
+                assert!(start.0 < 9, "{:?} is on the small island", start);
+                let field = FlowField::new(&map, &settings.costs, &[*start]);
+                for other in placement.starts.iter().skip(index + 1) {
+                    assert!(field.cost(other.0, other.1).is_some(), "{:?} can't reach {:?}", start, other);
+                    assert!(cell_distance(*start, *other) >= settings.min_distance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rerolls_move_only_the_starts() {
+        let map = two_islands();
+        let terrain: Vec<_> = map.cells().map(|(cell, map_cell)| (cell, *map_cell)).collect();
+        let rules = crate::features::default_rules();
+        let settings = settings();
+        let mut seeds = MapSeeds { features: 3, starts: 7, roll: 0 };
+
+        let (_, first) = populate(&map, &rules, &settings, &seeds);
+        let first = first.expect("the large island fits three players").starts;
+        let (_, again) = populate(&map, &rules, &settings, &seeds);
+        assert_eq!(again.map(|placement| placement.starts), Some(first.clone()));
+
+        let placed: Vec<_> = place_features(&map, &rules, &mut seeds.feature_rng()).iter().collect();
+        seeds.reroll();
+        let (_, rerolled) = populate(&map, &rules, &settings, &seeds);
+        assert_ne!(rerolled.map(|placement| placement.starts), Some(first));
+        // The terrain and the features before balancing stay as they were
+        assert_eq!(map.cells().map(|(cell, map_cell)| (cell, *map_cell)).collect::<Vec<_>>(), terrain);
+        assert_eq!((seeds.features, seeds.starts), (3, 7));
+        assert_eq!(place_features(&map, &rules, &mut seeds.feature_rng()).iter().collect::<Vec<_>>(), placed);
+    }
+
+    #[test]
+    fn too_many_players_find_no_starts() {
+        let map = two_islands();
+        let features = FeatureMap::new(map.width(), map.height());
+        let settings = StartSettings { players: 20, ..settings() };
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(find_starts(&map, &features, &settings, &mut rng).is_none());
+    }
+
+    #[test]
+    fn spread_of_equal_values_is_zero() {
+        assert_eq!(spread(&[2.5, 2.5, 2.5]), 0.0);
+        assert_eq!(spread(&[]), 0.0);
+        assert_eq!(spread(&[0.0, 0.0]), 0.0);
+        assert!((spread(&[1.0, 2.0]) - 0.5).abs() < 1e-6);
+    }
+}
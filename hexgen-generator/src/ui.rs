@@ -1,8 +1,17 @@
+use std::f32::consts::PI;
 use egui::{Context, Key, Visuals};
 use glium::{Display, Frame};
 use glium::glutin::surface::WindowSurface;
-use winit::window::Window;
-use crate::Generator;
+use tracing::error;
+use winit::window::{CursorGrabMode, Window};
+use hexgen_common::vector3::Vector3;
+use hexgen_renderer::camera::controller::{CameraMode, ControllerInput};
+use hexgen_renderer::camera::projection::ProjectionKind;
+use hexgen_renderer::light::{Light, LightKind, MAX_LIGHTS};
+use hexgen_renderer::time_of_day::Gradient;
+use crate::features::Feature;
+use crate::regions::RegionKind;
+use crate::{Generator, TerrainStyle};
 
 pub struct UI {
     width: u8,
@@ -11,6 +20,7 @@ pub struct UI {
     ss_width: u8,
     seed: u64,
     define_seed: bool,
+    pointer_locked: bool,
 }
 
 impl UI {
@@ -22,6 +32,7 @@ impl UI {
             define_seed: false,
             ss_width: 5,
             ss_height: 5,
+            pointer_locked: false,
         }
     }
 
@@ -30,9 +41,15 @@ impl UI {
             let mut visuals = Visuals::from(Visuals::dark());
             visuals.window_shadow.extrusion = 2.0;
             egui_ctx.set_visuals(visuals);
-            self.initialize_shortcuts(egui_ctx, generator);
-            self.generation_settings(generator, egui_ctx);
+            self.initialize_shortcuts(egui_ctx, generator, window);
+            self.generation_settings(generator, display, egui_ctx);
             self.scene_settings(generator, frame_rate, egui_ctx);
+            self.atmosphere_settings(generator, egui_ctx);
+            self.fog_of_war_settings(generator, egui_ctx);
+            self.start_settings(generator, display, egui_ctx);
+            self.time_of_day_settings(generator, egui_ctx);
+            self.light_settings(generator, egui_ctx);
+            self.asset_errors(generator, egui_ctx);
         });
 
         if repaint_after.is_zero() {
@@ -51,44 +68,74 @@ impl UI {
         }
     }
 
-    fn initialize_shortcuts(&mut self, egui_ctx: &Context, generator: &mut Generator) {
-        if egui_ctx.input(|i| i.key_pressed(Key::D)) {
-            generator.renderer.camera.position.z += 0.5;
-            generator.renderer.camera.recalculate();
-        }
-        if egui_ctx.input(|i| i.key_pressed(Key::A)) {
-            generator.renderer.camera.position.z -= 0.5;
-            generator.renderer.camera.recalculate();
-        }
-        if egui_ctx.input(|i| i.key_pressed(Key::S)) {
-            generator.renderer.camera.position.x += 0.5;
-            generator.renderer.camera.recalculate();
-        }
-        if egui_ctx.input(|i| i.key_pressed(Key::W)) {
-            generator.renderer.camera.position.x -= 0.5;
-            generator.renderer.camera.recalculate();
-        }
-        if egui_ctx.input(|i| i.key_pressed(Key::E)) {
-            generator.renderer.camera.position.y += 0.5;
-            generator.renderer.camera.recalculate();
+    fn initialize_shortcuts(&mut self, egui_ctx: &Context, generator: &mut Generator, window: &Window) {
+        let keyboard_free = !egui_ctx.wants_keyboard_input();
+        let pointer_over_ui = !self.pointer_locked && (egui_ctx.wants_pointer_input() || egui_ctx.is_pointer_over_area());
+        let axis = |i: &egui::InputState, positive: Key, negative: Key| {
+            if !keyboard_free { return 0.0; }
+            (i.key_down(positive) as i32 - i.key_down(negative) as i32) as f32
+        };
+        let (input, dt) = egui_ctx.input(|i| {
+            let screen_size = i.screen_rect().size();
+            let input = ControllerInput {
+                pointer_delta: (i.pointer.delta().x, i.pointer.delta().y),
+                pointer_position: i.pointer.hover_pos().map(|p| (p.x, p.y)),
+                screen_size: (screen_size.x, screen_size.y),
+                primary_down: i.pointer.primary_down(),
+                secondary_down: i.pointer.secondary_down(),
+                middle_down: i.pointer.middle_down(),
+                scroll: i.scroll_delta.y,
+                move_axis: Vector3::new(axis(i, Key::D, Key::A), axis(i, Key::E, Key::Q), axis(i, Key::W, Key::S)),
+                rotate_axis: axis(i, Key::X, Key::Z),
+                rotate_steps: if keyboard_free { i.key_pressed(Key::X) as i32 - i.key_pressed(Key::Z) as i32 } else { 0 },
+                boost: i.modifiers.shift,
+                pointer_over_ui,
+            };
+            (input, i.stable_dt)
+        });
+        generator.camera_controller.handle_input(&input, dt);
+
+        let wants_lock = generator.camera_controller.wants_pointer_lock();
+        if wants_lock != self.pointer_locked {
+            self.set_pointer_lock(window, wants_lock);
         }
-        if egui_ctx.input(|i| i.key_pressed(Key::Q)) {
-            generator.renderer.camera.position.y -= 0.5;
-            generator.renderer.camera.recalculate();
+
+        if keyboard_free && egui_ctx.input(|i| i.key_pressed(Key::F)) {
+            generator.frame_terrain();
         }
-        let scroll_delta = egui_ctx.input(|i| i.scroll_delta);
-        let sensitivity = 0.01;
-        if egui_ctx.input(|i| i.key_pressed(Key::Z)) {
-            generator.renderer.camera.direction.z += scroll_delta.y * sensitivity;
-            generator.renderer.camera.recalculate();
+        if keyboard_free {
+            let mode = egui_ctx.input(|i| {
+                if i.key_pressed(Key::Num1) {
+                    Some(CameraMode::Orbit)
+                } else if i.key_pressed(Key::Num2) {
+                    Some(CameraMode::Strategy)
+                } else if i.key_pressed(Key::Num3) {
+                    Some(CameraMode::FreeFly)
+                } else {
+                    None
+                }
+            });
+            if let Some(mode) = mode {
+                generator.camera_controller.set_mode(mode, &generator.renderer.camera);
+            }
         }
-        if egui_ctx.input(|i| i.key_pressed(Key::X)) {
-            generator.renderer.camera.direction.x += scroll_delta.y * sensitivity;
-            generator.renderer.camera.recalculate();
+    }
+
+    fn set_pointer_lock(&mut self, window: &Window, locked: bool) {
+        if locked {
+            if window.set_cursor_grab(CursorGrabMode::Locked).is_err() {
+                if let Err(e) = window.set_cursor_grab(CursorGrabMode::Confined) {
+                    error!("Couldn't grab the cursor: {}", e);
+                }
+            }
+        } else if let Err(e) = window.set_cursor_grab(CursorGrabMode::None) {
+            error!("Couldn't release the cursor: {}", e);
         }
+        window.set_cursor_visible(!locked);
+        self.pointer_locked = locked;
     }
 
-    fn generation_settings(&mut self, generator: &mut Generator, egui_ctx: &Context) {
+    fn generation_settings(&mut self, generator: &mut Generator, display: &Display<WindowSurface>, egui_ctx: &Context) {
         egui::Window::new("Generation").show(
             egui_ctx,
             |ui| {
@@ -111,16 +158,24 @@ impl UI {
                             ui.add(egui::DragValue::new(&mut self.seed));
                         });
                     }
+                    ui.add_space(7.0);
+                    ui.label("Terrain style:");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut generator.terrain_style, TerrainStyle::Models, "Tiles");
+                        ui.radio_value(&mut generator.terrain_style, TerrainStyle::Prisms, "Prisms");
+                        ui.radio_value(&mut generator.terrain_style, TerrainStyle::Smooth, "Smooth");
+                    });
                     ui.add_space(10.0);
 
 
                     ui.centered_and_justified(|ui| {
                         if ui.button("Generate").clicked() {
                             if self.define_seed {
-                                generator.generate_terrain_with_seed(self.width, self.height, self.seed);
+                                generator.generate_terrain_with_seed(self.width, self.height, self.seed, display);
                             } else {
-                                generator.generate_terrain_without_seed(self.width, self.height);
+                                generator.generate_terrain_without_seed(self.width, self.height, display);
                             }
+                            generator.frame_terrain();
                             self.ss_height = self.height;
                             self.ss_width = self.width;
                         }
@@ -133,35 +188,109 @@ impl UI {
 
     fn scene_settings(&mut self, generator: &mut Generator, frame_rate: f64, egui_ctx: &Context) {
         egui::Window::new("Scene").show(egui_ctx, |ui| {
-            ui.label("Camera position:");
+            ui.label("Camera mode:");
             ui.horizontal(|ui| {
-                let response_x = ui.add(egui::DragValue::new(&mut generator.renderer.camera.position.x));
-                let response_y = ui.add(egui::DragValue::new(&mut generator.renderer.camera.position.y));
-                let response_z = ui.add(egui::DragValue::new(&mut generator.renderer.camera.position.z));
-
-                if response_x.changed() || response_y.changed() || response_z.changed() {
-                    generator.renderer.camera.recalculate();
-                }
+                let mut mode = generator.camera_controller.mode();
+                ui.radio_value(&mut mode, CameraMode::Orbit, "Orbit");
+                ui.radio_value(&mut mode, CameraMode::Strategy, "Strategy");
+                ui.radio_value(&mut mode, CameraMode::FreeFly, "Free fly");
+                generator.camera_controller.set_mode(mode, &generator.renderer.camera);
             });
 
-            ui.label("Camera direction:");
+            ui.label("Projection:");
             ui.horizontal(|ui| {
-                let response_x = ui.add(egui::DragValue::new(&mut generator.renderer.camera.direction.x));
-                let response_y = ui.add(egui::DragValue::new(&mut generator.renderer.camera.direction.y));
-                let response_z = ui.add(egui::DragValue::new(&mut generator.renderer.camera.direction.z));
-
-                if response_x.changed() || response_y.changed() || response_z.changed() {
-                    generator.renderer.camera.recalculate();
-                }
+                let mut kind = generator.renderer.camera.projection.kind();
+                ui.radio_value(&mut kind, ProjectionKind::Perspective, "Perspective");
+                ui.radio_value(&mut kind, ProjectionKind::Orthographic, "Orthographic");
+                generator.renderer.camera.set_projection_kind(kind);
             });
 
-            ui.label("Light direction:");
+            ui.label("View presets:");
             ui.horizontal(|ui| {
-                ui.add(egui::DragValue::new(&mut generator.renderer.directional_light.direction.x));
-                ui.add(egui::DragValue::new(&mut generator.renderer.directional_light.direction.y));
-                ui.add(egui::DragValue::new(&mut generator.renderer.directional_light.direction.z));
+                let isometric = ui.button("Isometric").clicked();
+                let side = ui.button("Side").clicked();
+                let top = ui.button("Top").clicked();
+                let preset = if isometric {
+                    Some((PI / 4.0, -(1.0 / 2.0f32.sqrt()).atan()))
+                } else if side {
+                    Some((PI / 2.0, 0.0))
+                } else if top {
+                    Some((PI / 2.0, -PI / 2.0))
+                } else {
+                    None
+                };
+                if let Some((yaw, pitch)) = preset {
+                    generator.renderer.camera.set_projection_kind(ProjectionKind::Orthographic);
+                    generator.camera_controller.set_mode(CameraMode::Orbit, &generator.renderer.camera);
+                    generator.camera_controller.orbit.set_angles(yaw, pitch);
+                }
             });
 
+            match generator.camera_controller.mode() {
+                CameraMode::Orbit => {
+                    ui.label("Camera focus:");
+                    ui.horizontal(|ui| {
+                        let mut focus = generator.camera_controller.orbit.focus;
+                        let response_x = ui.add(egui::DragValue::new(&mut focus.x).speed(0.1));
+                        let response_y = ui.add(egui::DragValue::new(&mut focus.y).speed(0.1));
+                        let response_z = ui.add(egui::DragValue::new(&mut focus.z).speed(0.1));
+
+                        if response_x.changed() || response_y.changed() || response_z.changed() {
+                            generator.camera_controller.orbit.set_focus(focus);
+                        }
+                    });
+
+                    ui.label("Zoom limits:");
+                    ui.horizontal(|ui| {
+                        let controller = &mut generator.camera_controller.orbit;
+                        ui.add(egui::DragValue::new(&mut controller.min_distance).speed(0.1).clamp_range(0.1..=controller.max_distance));
+                        ui.add(egui::DragValue::new(&mut controller.max_distance).speed(0.1).clamp_range(controller.min_distance..=1000.0));
+                    });
+
+                    ui.label("Camera damping:");
+                    ui.add(egui::Slider::new(&mut generator.camera_controller.orbit.damping, 1.0..=30.0));
+                }
+                CameraMode::Strategy => {
+                    let controller = &mut generator.camera_controller.rts;
+                    ui.checkbox(&mut controller.edge_panning, "Edge panning");
+                    ui.label("Pan speed:");
+                    ui.add(egui::Slider::new(&mut controller.pan_speed, 0.1..=5.0));
+                    ui.label("Pitch:");
+                    ui.add(egui::Slider::new(&mut controller.pitch, -1.5..=-0.2));
+                    ui.horizontal(|ui| {
+                        if ui.button("Rotate left").clicked() {
+                            controller.rotate(-1);
+                        }
+                        if ui.button("Rotate right").clicked() {
+                            controller.rotate(1);
+                        }
+                    });
+                }
+                CameraMode::FreeFly => {
+                    let controller = &mut generator.camera_controller.fly;
+                    ui.label("Hold right mouse button to look around");
+                    ui.label("Move speed:");
+                    ui.add(egui::Slider::new(&mut controller.move_speed, 0.1..=100.0).logarithmic(true));
+                    ui.label("Look sensitivity:");
+                    ui.add(egui::Slider::new(&mut controller.look_sensitivity, 0.0005..=0.01));
+                }
+            }
+
+            if ui.button("Frame map").clicked() {
+                generator.frame_terrain();
+            }
+
+            ui.add_space(10.0);
+
+            if !generator.time_of_day.enabled {
+                ui.label("Light direction:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut generator.renderer.directional_light.direction.x));
+                    ui.add(egui::DragValue::new(&mut generator.renderer.directional_light.direction.y));
+                    ui.add(egui::DragValue::new(&mut generator.renderer.directional_light.direction.z));
+                });
+            }
+
             ui.add_space(10.0);
 
             ui.horizontal(|ui| {
@@ -176,6 +305,287 @@ impl UI {
                 ui.label("Height:");
                 ui.label(self.ss_height.to_string());
             });
+            let regions = &generator.regions;
+            ui.horizontal(|ui| {
+                ui.label("Land masses:");
+                ui.label(regions.regions_of(RegionKind::Land).count().to_string());
+            });
+            ui.horizontal(|ui| {
+                ui.label("Oceans / lakes:");
+                ui.label(format!("{} / {}", regions.regions_of(RegionKind::Ocean).count(), regions.regions_of(RegionKind::Lake).count()));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Coastline:");
+                ui.label(format!("{} cells", regions.coastline().len()));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Watersheds:");
+                ui.label(regions.watersheds().len().to_string());
+            });
+            for feature in Feature::ALL {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", feature.name()));
+                    ui.label(generator.features.count(feature).to_string());
+                });
+            }
+        });
+    }
+
+    fn atmosphere_settings(&mut self, generator: &mut Generator, egui_ctx: &Context) {
+        egui::Window::new("Atmosphere").default_open(false).show(egui_ctx, |ui| {
+            let atmosphere = &mut generator.renderer.atmosphere;
+            ui.checkbox(&mut atmosphere.sky_enabled, "Sky");
+            if atmosphere.sky_enabled {
+                UI::color_edit(ui, "Zenith color:", &mut atmosphere.zenith_color);
+                UI::color_edit(ui, "Horizon color:", &mut atmosphere.horizon_color);
+                UI::color_edit(ui, "Ground color:", &mut atmosphere.ground_color);
+                UI::color_edit(ui, "Sun color:", &mut atmosphere.sun_color);
+                ui.label("Sun size:");
+                ui.add(egui::Slider::new(&mut atmosphere.sun_size, 0.005..=0.2));
+            } else {
+                UI::color_edit(ui, "Background color:", &mut generator.renderer.background_color);
+            }
+
+            ui.add_space(10.0);
+
+            ui.checkbox(&mut atmosphere.fog_enabled, "Fog");
+            if atmosphere.fog_enabled {
+                ui.horizontal(|ui| {
+                    UI::color_edit(ui, "Fog color:", &mut atmosphere.fog_color);
+                    if ui.button("Match horizon").clicked() {
+                        atmosphere.fog_color = atmosphere.horizon_color;
+                    }
+                });
+                ui.label("Density:");
+                ui.add(egui::Slider::new(&mut atmosphere.fog_density, 0.0..=0.5).logarithmic(true));
+                ui.label("Start distance:");
+                ui.add(egui::Slider::new(&mut atmosphere.fog_start, 0.0..=100.0));
+                ui.label("Height falloff:");
+                ui.add(egui::Slider::new(&mut atmosphere.fog_height_falloff, 0.0..=5.0));
+            }
+        });
+    }
+
+    fn fog_of_war_settings(&mut self, generator: &mut Generator, egui_ctx: &Context) {
+        egui::Window::new("Fog of war").default_open(false).show(egui_ctx, |ui| {
+            ui.checkbox(&mut generator.renderer.fog_of_war.enabled, "Enabled");
+            if !generator.renderer.fog_of_war.enabled {
+                return;
+            }
+            ui.label("The map is revealed around the camera focus");
+            let sight = &mut generator.sight;
+            ui.label("Sight range:");
+            let range = ui.add(egui::Slider::new(&mut sight.range, 1..=12));
+            ui.label("Eye height:");
+            let eye_height = ui.add(egui::Slider::new(&mut sight.eye_height, 0.0..=2.0));
+            ui.label("Hill bonus:");
+            let hill_bonus = ui.add(egui::Slider::new(&mut sight.hill_bonus, 0..=4));
+            ui.label("Mountain bonus:");
+            let mountain_bonus = ui.add(egui::Slider::new(&mut sight.mountain_bonus, 0..=4));
+            if range.changed() || eye_height.changed() || hill_bonus.changed() || mountain_bonus.changed() {
+                generator.observer = None;
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Reveal all").clicked() {
+                    generator.renderer.fog_of_war.reveal_all();
+                }
+                if ui.button("Reset exploration").clicked() {
+                    generator.renderer.fog_of_war.reset();
+                    generator.observer = None;
+                }
+            });
+        });
+    }
+
+    fn start_settings(&mut self, generator: &mut Generator, display: &Display<WindowSurface>, egui_ctx: &Context) {
+        egui::Window::new("Starts").default_open(false).show(egui_ctx, |ui| {
+            let settings = &mut generator.start_settings;
+            ui.label("Players:");
+            ui.add(egui::Slider::new(&mut settings.players, 1..=8));
+            ui.label("Value radius:");
+            ui.add(egui::Slider::new(&mut settings.radius, 1..=6));
+            ui.label("Min distance:");
+            ui.add(egui::Slider::new(&mut settings.min_distance, 1..=20));
+            ui.label("Attempts:");
+            ui.add(egui::Slider::new(&mut settings.attempts, 1..=256));
+
+            // Features are placed again as well, balancing only ever adds to or removes from a fresh set
+            ui.horizontal(|ui| {
+                if ui.button("Place starts").clicked() {
+                    generator.populate(display);
+                }
+                if ui.button("Re-roll starts").clicked() {
+                    generator.reroll_starts(display);
+                }
+            });
+
+            ui.add_space(7.0);
+            match &generator.starts {
+                Some(placement) => {
+                    ui.label(format!("Fairness: {:.2}", placement.fairness));
+                    for (player, start) in placement.starts.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Player {}:", player + 1));
+                            ui.label(format!("{},{} value {:.1}, nearest {:.1}", start.0, start.1, placement.values[player], placement.nearest[player]));
+                        });
+                    }
+                }
+                None => {
+                    ui.label("No room for the starts on this map");
+                }
+            }
+        });
+    }
+
+    fn time_of_day_settings(&mut self, generator: &mut Generator, egui_ctx: &Context) {
+        egui::Window::new("Time of day").default_open(false).show(egui_ctx, |ui| {
+            let time_of_day = &mut generator.time_of_day;
+            ui.checkbox(&mut time_of_day.enabled, "Day/night cycle");
+            if !time_of_day.enabled {
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                let label = if time_of_day.paused { "Resume" } else { "Pause" };
+                if ui.button(label).clicked() {
+                    time_of_day.paused = !time_of_day.paused;
+                }
+                let hours = time_of_day.hour.floor();
+                ui.label(format!("{:02}:{:02}", hours as u32, ((time_of_day.hour - hours) * 60.0) as u32));
+            });
+            ui.label("Time:");
+            ui.add(egui::Slider::new(&mut time_of_day.hour, 0.0..=23.99));
+            ui.label("Speed (hours per second):");
+            ui.add(egui::Slider::new(&mut time_of_day.speed, 0.01..=12.0).logarithmic(true));
+            ui.label("Sun azimuth:");
+            ui.add(egui::Slider::new(&mut time_of_day.sun_azimuth, 0.0..=2.0 * PI));
+            ui.label("Sun tilt:");
+            ui.add(egui::Slider::new(&mut time_of_day.sun_tilt, 0.0..=1.2));
+
+            ui.collapsing("Gradients", |ui| {
+                UI::gradient_edit(ui, "Light color", &mut time_of_day.light_color);
+                UI::gradient_edit(ui, "Ambient color", &mut time_of_day.ambient_color);
+                UI::gradient_edit(ui, "Zenith color", &mut time_of_day.zenith_color);
+                UI::gradient_edit(ui, "Horizon color", &mut time_of_day.horizon_color);
+                ui.label("Light intensity");
+                for (hour, intensity) in time_of_day.light_intensity.values_mut() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{:05.2}", hour));
+                        ui.add(egui::Slider::new(intensity, 0.0..=2.0));
+                    });
+                }
+            });
+        });
+    }
+
+    fn gradient_edit(ui: &mut egui::Ui, label: &str, gradient: &mut Gradient<Vector3>) {
+        ui.label(label);
+        ui.horizontal_wrapped(|ui| {
+            for (hour, color) in gradient.values_mut() {
+                UI::color_edit(ui, &format!("{:05.2}", hour), color);
+            }
+        });
+    }
+
+    fn light_settings(&mut self, generator: &mut Generator, egui_ctx: &Context) {
+        egui::Window::new("Lights").default_open(false).show(egui_ctx, |ui| {
+            let lights = &mut generator.renderer.lights;
+            ui.horizontal(|ui| {
+                if ui.button("Add point light").clicked() {
+                    let name = format!("Point light {}", lights.len());
+                    lights.push(Light::point(name, Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.6, 0.3), 2.0, 3.0));
+                }
+                if ui.button("Add spot light").clicked() {
+                    let name = format!("Spot light {}", lights.len());
+                    lights.push(Light::spot(name, Vector3::new(0.0, 2.0, 0.0), Vector3::down(), Vector3::one(), 3.0, 5.0));
+                }
+            });
+            if lights.len() > MAX_LIGHTS {
+                ui.label(format!("Only the {} lights closest to the camera are rendered", MAX_LIGHTS));
+            }
+
+            let mut removed = None;
+            for (i, light) in lights.iter_mut().enumerate() {
+                egui::CollapsingHeader::new(light.name.clone()).id_source(i).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut light.enabled, "Enabled");
+                        if ui.button("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                    ui.text_edit_singleline(&mut light.name);
+                    UI::color_edit(ui, "Color:", &mut light.color);
+                    ui.label("Intensity:");
+                    ui.add(egui::Slider::new(&mut light.intensity, 0.0..=10.0));
+                    ui.label("Range:");
+                    ui.add(egui::Slider::new(&mut light.range, 0.1..=20.0));
+                    ui.label("Attenuation:");
+                    ui.add(egui::Slider::new(&mut light.attenuation, 0.0..=10.0));
+
+                    let mut attached = light.attached_to.is_some();
+                    if ui.checkbox(&mut attached, "Attach to game object").changed() {
+                        light.attached_to = if attached { Some(String::new()) } else { None };
+                    }
+                    if let Some(name) = &mut light.attached_to {
+                        ui.text_edit_singleline(name);
+                    }
+                    ui.label(if light.attached_to.is_some() { "Offset:" } else { "Position:" });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut light.position.x).speed(0.05));
+                        ui.add(egui::DragValue::new(&mut light.position.y).speed(0.05));
+                        ui.add(egui::DragValue::new(&mut light.position.z).speed(0.05));
+                    });
+
+                    if light.kind == LightKind::Spot {
+                        ui.label("Direction:");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut light.direction.x).speed(0.05));
+                            ui.add(egui::DragValue::new(&mut light.direction.y).speed(0.05));
+                            ui.add(egui::DragValue::new(&mut light.direction.z).speed(0.05));
+                        });
+                        ui.label("Cone angles:");
+                        ui.add(egui::Slider::new(&mut light.inner_angle, 0.0..=light.outer_angle));
+                        ui.add(egui::Slider::new(&mut light.outer_angle, light.inner_angle..=PI / 2.0));
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                lights.remove(i);
+            }
+        });
+    }
+
+    fn asset_errors(&mut self, generator: &mut Generator, egui_ctx: &Context) {
+        let missing = generator.assets.errors();
+        let shader_errors = generator.assets.shaders.errors();
+        if missing.is_empty() && shader_errors.is_empty() {
+            return;
+        }
+        egui::Window::new("Asset errors").show(egui_ctx, |ui| {
+            egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                for message in missing {
+                    ui.label(egui::RichText::new(message).color(egui::Color32::LIGHT_RED));
+                }
+                if !shader_errors.is_empty() {
+                    ui.label("Fix the shader sources and save, the previous programs stay in use until then");
+                }
+                for (shader, error) in shader_errors {
+                    ui.separator();
+                    ui.strong(format!("{} + {}", shader.vertex_path, shader.fragment_path));
+                    ui.label(egui::RichText::new(error).monospace().color(egui::Color32::LIGHT_RED));
+                }
+            });
+        });
+    }
+
+    fn color_edit(ui: &mut egui::Ui, label: &str, color: &mut Vector3) {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            let mut rgb: [f32; 3] = (*color).into();
+            if ui.color_edit_button_rgb(&mut rgb).changed() {
+                *color = Vector3::from(rgb);
+            }
         });
     }
 }
\ No newline at end of file
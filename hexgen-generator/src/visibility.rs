@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+use crate::map::{hex_distance, hex_line, ring, Terrain, TerrainMap};
+
+// How far and from how high an observer sees. Elevations come from the map, so stacked tiles
+// both lift the observer and hide what lies behind them
+#[derive(Clone, Debug)]
+pub struct Sight {
+    // In cells, on flat terrain
+    pub range: u32,
+    // Eye above the surface of the observer's cell, in world units
+    pub eye_height: f32,
+    // Extra cells of range when standing on a hill
+    pub hill_bonus: u32,
+    // Extra cells of range when standing on a mountain
+    pub mountain_bonus: u32,
+}
+
+impl Default for Sight {
+    fn default() -> Self {
+        Sight {
+            range: 3,
+            // One tile layer, enough to look over a neighbour of the same height
+            eye_height: 0.1,
+            hill_bonus: 1,
+            mountain_bonus: 2,
+        }
+    }
+}
+
+impl Sight {
+    // Range from the given cell, including the bonus of its terrain
+    pub fn range_from(&self, map: &TerrainMap, cell: (u8, u8)) -> u32 {
+        match map.get(cell.0, cell.1).map(|cell| cell.terrain) {
+            Some(Terrain::GrassHill | Terrain::StoneHill) => self.range + self.hill_bonus,
+            Some(Terrain::StoneMountain) => self.range + self.mountain_bonus,
+            _ => self.range,
+        }
+    }
+
+    fn eye(&self, map: &TerrainMap, cell: (u8, u8)) -> f32 {
+        map.get(cell.0, cell.1).map_or(0.0, |cell| cell.elevation) + self.eye_height
+    }
+}
+
+// Rise of a cell over the eye per step of distance, a cell hides everything behind it with a lower slope.
+// Mountains hide everything behind them
+fn slope(map: &TerrainMap, cell: (u8, u8), eye: f32, distance: i32) -> f32 {
+    match map.get(cell.0, cell.1) {
+        Some(cell) if cell.terrain == Terrain::StoneMountain => f32::INFINITY,
+        Some(cell) => (cell.elevation - eye) / distance as f32,
+        None => f32::NEG_INFINITY,
+    }
+}
+
+// Surface slope of a cell, mountains themselves are seen like any other cell
+fn surface_slope(map: &TerrainMap, cell: (u8, u8), eye: f32, distance: i32) -> f32 {
+    map.get(cell.0, cell.1).map_or(f32::NEG_INFINITY, |cell| (cell.elevation - eye) / distance as f32)
+}
+
+// Whether the surface of to can be seen from an observer standing on from, within range.
+// Uses the same shadows as field_of_view, so both always agree
+pub fn line_of_sight(map: &TerrainMap, sight: &Sight, from: (u8, u8), to: (u8, u8)) -> bool {
+    if map.get(from.0, from.1).is_none() || map.get(to.0, to.1).is_none() {
+        return false;
+    }
+    let (origin, cell) = ((from.0 as i32, from.1 as i32), (to.0 as i32, to.1 as i32));
+    let distance = hex_distance(origin, cell);
+    if distance > sight.range_from(map, from) as i32 {
+        return false;
+    }
+    let eye = sight.eye(map, from);
+    distance == 0 || surface_slope(map, to, eye, distance) >= shadow(map, eye, origin, cell, distance, &mut HashMap::new())
+}
+
+// Every cell visible from observer, the observer's own cell included. Shadows are cast outwards ring by ring,
+// so every cell finds the shadows of the cells it's seen through already worked out and the whole field
+// costs one pass over the cells in range
+pub fn field_of_view(map: &TerrainMap, sight: &Sight, observer: (u8, u8)) -> HashSet<(u8, u8)> {
+    let mut visible = HashSet::new();
+    if map.get(observer.0, observer.1).is_none() {
+        return visible;
+    }
+    visible.insert(observer);
+    let origin = (observer.0 as i32, observer.1 as i32);
+    let range = sight.range_from(map, observer) as i32;
+    let eye = sight.eye(map, observer);
+
+    let mut shadows = HashMap::new();
+    for distance in 1..=range {
+        for cell in ring(origin, distance) {
+            if !map.contains(cell.0, cell.1) {
+                continue;
+            }
+            let shadow = shadow(map, eye, origin, cell, distance, &mut shadows);
+            let cell = (cell.0 as u8, cell.1 as u8);
+            if surface_slope(map, cell, eye, distance) >= shadow {
+                visible.insert(cell);
+            }
+        }
+    }
+    visible
+}
+
+// Steepest slope between the observer and a cell, inherited from the cells one step closer on both lines
+// towards the observer. The less shadowed one wins, so a line along a cell edge is clear when either side
+// of it is. Shadows are remembered per cell, both lines usually lead through the same cells
+fn shadow(map: &TerrainMap, eye: f32, origin: (i32, i32), cell: (i32, i32), distance: i32, shadows: &mut HashMap<(i32, i32), f32>) -> f32 {
+    if distance <= 1 {
+        return f32::NEG_INFINITY;
+    }
+    if let Some(shadow) = shadows.get(&cell) {
+        return *shadow;
+    }
+    let towards = hex_line(cell, origin)[1];
+    let away = hex_line(origin, cell)[distance as usize - 1];
+    let shadow = shadow_behind(map, eye, origin, towards, distance - 1, shadows)
+        .min(shadow_behind(map, eye, origin, away, distance - 1, shadows));
+    shadows.insert(cell, shadow);
+    shadow
+}
+
+// Shadow on the cells right behind a cell, cells outside the map are never blocked
+fn shadow_behind(map: &TerrainMap, eye: f32, origin: (i32, i32), cell: (i32, i32), distance: i32, shadows: &mut HashMap<(i32, i32), f32>) -> f32 {
+    if !map.contains(cell.0, cell.1) {
+        return f32::NEG_INFINITY;
+    }
+    shadow(map, eye, origin, cell, distance, shadows).max(slope(map, (cell.0 as u8, cell.1 as u8), eye, distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::tests::map_from_rows;
+    use crate::map::MapCell;
+
+    #[test]
+    fn mountain_blocks_line_of_sight() {
+        let mut map = map_from_rows(&[
+            ".....",
+            ".....",
+            ".....",
+            ".....",
+            ".....",
+        ]);
+        let sight = Sight::default();
+        assert!(line_of_sight(&map, &sight, (2, 0), (2, 3)));
+        map.set(2, 1, MapCell { terrain: Terrain::StoneMountain, elevation: 0.1 });
+        assert!(line_of_sight(&map, &sight, (2, 0), (2, 1)));
+        assert!(!line_of_sight(&map, &sight, (2, 0), (2, 2)));
+        assert!(!line_of_sight(&map, &sight, (2, 0), (2, 3)));
+        // Cells beside the mountain are still in sight
+        assert!(line_of_sight(&map, &sight, (2, 0), (0, 1)));
+    }
+
+    #[test]
+    fn hills_add_range() {
+        let mut map = map_from_rows(&[".........."]);
+        let sight = Sight::default();
+        assert_eq!(sight.range_from(&map, (0, 0)), 3);
+        assert!(line_of_sight(&map, &sight, (0, 0), (3, 0)));
+        assert!(!line_of_sight(&map, &sight, (0, 0), (4, 0)));
+
+        map.set(0, 0, MapCell { terrain: Terrain::GrassHill, elevation: 0.1 });
+        assert_eq!(sight.range_from(&map, (0, 0)), 3 + sight.hill_bonus);
+        assert!(line_of_sight(&map, &sight, (0, 0), (4, 0)));
+        assert!(!line_of_sight(&map, &sight, (0, 0), (5, 0)));
+        assert_eq!(field_of_view(&map, &sight, (0, 0)).len(), 5);
+    }
+
+    #[test]
+    fn higher_cells_hide_what_lies_behind() {
+        let mut map = map_from_rows(&["......"]);
+        map.get_mut(2, 0).unwrap().elevation = 0.3;
+        let low = Sight::default();
+        assert!(line_of_sight(&map, &low, (0, 0), (2, 0)));
+        assert!(!line_of_sight(&map, &low, (0, 0), (3, 0)));
+        let high = Sight { eye_height: 1.0, ..Sight::default() };
+        assert!(line_of_sight(&map, &high, (0, 0), (3, 0)));
+    }
+
+    #[test]
+    fn field_of_view_matches_line_of_sight() {
+        let mut map = map_from_rows(&[
+            "....f.....",
+            "..M...h...",
+            ".....M....",
+            "...h...o..",
+            ".M........",
+            "....~~.M..",
+            "..o.~~....",
+            "......h...",
+        ]);
+        for ((x, z), cell) in map.clone().cells() {
+            if !cell.terrain.is_water() {
+                map.get_mut(x, z).unwrap().elevation = 0.1 * ((x as u32 * 7 + z as u32 * 3) % 4) as f32;
+            }
+        }
+        let sight = Sight { range: 4, ..Sight::default() };
+        for (observer, _) in map.cells() {
+            let visible = field_of_view(&map, &sight, observer);
+            for (cell, _) in map.cells() {
+                assert_eq!(visible.contains(&cell), line_of_sight(&map, &sight, observer, cell), "{:?} seeing {:?}", observer, cell);
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use hexgen_common::vector3::Vector3;
+
+pub struct Atmosphere {
+    pub sky_enabled: bool,
+    pub zenith_color: Vector3,
+    pub horizon_color: Vector3,
+    pub ground_color: Vector3,
+    pub sun_color: Vector3,
+    // Angular radius of the sun disk in radians
+    pub sun_size: f32,
+    pub fog_enabled: bool,
+    pub fog_color: Vector3,
+    pub fog_density: f32,
+    // View distance at which the fog starts
+    pub fog_start: f32,
+    // How quickly the fog thins out with height, 0 gives uniform fog
+    pub fog_height_falloff: f32,
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Atmosphere::new()
+    }
+}
+
+impl Atmosphere {
+    pub fn new() -> Atmosphere {
+        Atmosphere {
+            sky_enabled: true,
+            zenith_color: Vector3::new(0.18, 0.36, 0.68),
+            horizon_color: Vector3::new(0.68, 0.8, 0.9),
+            ground_color: Vector3::new(0.32, 0.36, 0.4),
+            sun_color: Vector3::new(1.0, 0.95, 0.8),
+            sun_size: 0.03,
+            fog_enabled: true,
+            fog_color: Vector3::new(0.68, 0.8, 0.9),
+            fog_density: 0.04,
+            fog_start: 8.0,
+            fog_height_falloff: 0.5,
+        }
+    }
+
+    // Density handed to the shaders, disabled fog is just fog without density
+    pub fn effective_fog_density(&self) -> f32 {
+        if self.fog_enabled { self.fog_density } else { 0.0 }
+    }
+}
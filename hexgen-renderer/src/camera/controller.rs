@@ -0,0 +1,142 @@
+pub mod orbit;
+pub mod rts;
+pub mod fly;
+
+use hexgen_common::vector3::Vector3;
+use crate::camera::Camera;
+use crate::camera::controller::fly::FlyController;
+use crate::camera::controller::orbit::OrbitController;
+use crate::camera::controller::rts::RtsController;
+use crate::camera::projection::Projection;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CameraMode {
+    Orbit,
+    Strategy,
+    FreeFly,
+}
+
+#[derive(Default)]
+pub struct ControllerInput {
+    pub pointer_delta: (f32, f32),
+    pub pointer_position: Option<(f32, f32)>,
+    pub screen_size: (f32, f32),
+    pub primary_down: bool,
+    pub secondary_down: bool,
+    pub middle_down: bool,
+    pub scroll: f32,
+    // x - right, y - up, z - forward
+    pub move_axis: Vector3,
+    pub rotate_axis: f32,
+    pub rotate_steps: i32,
+    pub boost: bool,
+    pub pointer_over_ui: bool,
+}
+
+pub struct CameraController {
+    mode: CameraMode,
+    pub orbit: OrbitController,
+    pub rts: RtsController,
+    pub fly: FlyController,
+}
+
+impl CameraController {
+    pub fn new(camera: &Camera, distance: f32) -> CameraController {
+        CameraController {
+            mode: CameraMode::Orbit,
+            orbit: OrbitController::from_camera(camera, distance),
+            rts: RtsController::from_camera(camera, distance),
+            fly: FlyController::from_camera(camera),
+        }
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    // Picks up the new controller from the current camera so switching does not jump
+    pub fn set_mode(&mut self, mode: CameraMode, camera: &Camera) {
+        if self.mode == mode {
+            return;
+        }
+        match mode {
+            CameraMode::Orbit => self.orbit.sync(camera),
+            CameraMode::Strategy => self.rts.sync(camera),
+            CameraMode::FreeFly => self.fly.sync(camera),
+        }
+        self.mode = mode;
+    }
+
+    // Point the camera looks at, the camera itself when flying
+    pub fn focus(&self, camera: &Camera) -> Vector3 {
+        match self.mode {
+            CameraMode::Orbit => self.orbit.focus,
+            CameraMode::Strategy => self.rts.focus,
+            CameraMode::FreeFly => camera.position,
+        }
+    }
+
+    pub fn wants_pointer_lock(&self) -> bool {
+        self.mode == CameraMode::FreeFly && self.fly.is_looking()
+    }
+
+    pub fn handle_input(&mut self, input: &ControllerInput, delta_time: f32) {
+        match self.mode {
+            CameraMode::Orbit => self.orbit.handle_input(input, delta_time),
+            CameraMode::Strategy => self.rts.handle_input(input, delta_time),
+            CameraMode::FreeFly => self.fly.handle_input(input, delta_time),
+        }
+    }
+
+    pub fn mouse_motion(&mut self, delta_x: f32, delta_y: f32) {
+        if self.mode == CameraMode::FreeFly {
+            self.fly.look(delta_x, delta_y);
+        }
+    }
+
+    pub fn frame(&mut self, min: Vector3, max: Vector3, projection: &dyn Projection) {
+        let extent = max - min;
+        let radius = extent.length() / 2.0;
+        let center = (min + max) * 0.5;
+        let distance = projection.framing_distance(radius);
+        self.orbit.frame(center, distance);
+        self.rts.set_bounds(min, max);
+        self.rts.frame(center, distance);
+        self.fly.frame(center, distance);
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, delta_time: f32) {
+        match self.mode {
+            CameraMode::Orbit => {
+                self.orbit.update(camera, delta_time);
+                camera.projection.set_view_distance(self.orbit.distance);
+            }
+            CameraMode::Strategy => {
+                self.rts.update(camera, delta_time);
+                camera.projection.set_view_distance(self.rts.distance);
+            }
+            CameraMode::FreeFly => self.fly.update(camera, delta_time),
+        }
+    }
+}
+
+// Exponential smoothing weight for the given damping, independent of the frame rate
+pub fn smoothing_factor(damping: f32, delta_time: f32) -> f32 {
+    1.0 - (-damping * delta_time).exp()
+}
+
+pub fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+pub fn lerp_vector(from: Vector3, to: Vector3, t: f32) -> Vector3 {
+    from.lerp(to, t)
+}
+
+pub fn direction_from_angles(yaw: f32, pitch: f32) -> Vector3 {
+    Vector3::new(pitch.cos() * yaw.cos(), pitch.sin(), pitch.cos() * yaw.sin())
+}
+
+pub fn angles_from_direction(direction: &Vector3) -> (f32, f32) {
+    (direction.z.atan2(direction.x), (direction.y / direction.length()).asin())
+}
@@ -0,0 +1,88 @@
+use std::f32::consts::PI;
+use hexgen_common::vector3::Vector3;
+use crate::camera::Camera;
+use crate::camera::controller::{angles_from_direction, direction_from_angles, lerp_vector, smoothing_factor, ControllerInput};
+
+pub struct FlyController {
+    pub position: Vector3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub move_speed: f32,
+    pub boost_multiplier: f32,
+    pub look_sensitivity: f32,
+    pub damping: f32,
+    velocity: Vector3,
+    looking: bool,
+}
+
+impl FlyController {
+    pub fn new(position: Vector3, yaw: f32, pitch: f32) -> FlyController {
+        FlyController {
+            position,
+            yaw,
+            pitch,
+            move_speed: 4.0,
+            boost_multiplier: 3.0,
+            look_sensitivity: 0.003,
+            damping: 8.0,
+            velocity: Vector3::zero(),
+            looking: false,
+        }
+    }
+
+    pub fn from_camera(camera: &Camera) -> FlyController {
+        let mut controller = FlyController::new(camera.position, 0.0, 0.0);
+        controller.sync(camera);
+        controller
+    }
+
+    pub fn sync(&mut self, camera: &Camera) {
+        let (yaw, pitch) = angles_from_direction(&camera.direction);
+        self.position = camera.position;
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.velocity = Vector3::zero();
+    }
+
+    // Mouse look is active while the secondary button is held, the pointer is locked meanwhile
+    pub fn is_looking(&self) -> bool {
+        self.looking
+    }
+
+    pub fn handle_input(&mut self, input: &ControllerInput, delta_time: f32) {
+        self.looking = input.secondary_down && (self.looking || !input.pointer_over_ui);
+
+        let forward = direction_from_angles(self.yaw, self.pitch);
+        let right = Vector3::new(self.yaw.sin(), 0.0, -self.yaw.cos());
+        let speed = self.move_speed * if input.boost { self.boost_multiplier } else { 1.0 };
+        let target_velocity = (right * input.move_axis.x + Vector3::up() * input.move_axis.y + forward * input.move_axis.z) * speed;
+
+        let t = smoothing_factor(self.damping, delta_time);
+        self.velocity = lerp_vector(self.velocity, target_velocity, t);
+        if !input.pointer_over_ui {
+            self.move_speed = (self.move_speed * (input.scroll * 0.002).exp()).clamp(0.1, 100.0);
+        }
+    }
+
+    pub fn look(&mut self, delta_x: f32, delta_y: f32) {
+        if !self.looking {
+            return;
+        }
+        self.yaw -= delta_x * self.look_sensitivity;
+        self.pitch = (self.pitch - delta_y * self.look_sensitivity).clamp(-PI / 2.0 + 0.01, PI / 2.0 - 0.01);
+    }
+
+    pub fn frame(&mut self, center: Vector3, distance: f32) {
+        let direction = direction_from_angles(self.yaw, self.pitch);
+        self.position = center - direction * distance;
+        self.velocity = Vector3::zero();
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, delta_time: f32) {
+        self.position += self.velocity * delta_time;
+
+        camera.direction = direction_from_angles(self.yaw, self.pitch);
+        camera.position = self.position;
+        camera.recalculate();
+    }
+}
@@ -0,0 +1,127 @@
+use std::f32::consts::PI;
+use hexgen_common::vector3::Vector3;
+use crate::camera::Camera;
+use crate::camera::controller::{angles_from_direction, direction_from_angles, lerp, lerp_vector, smoothing_factor, ControllerInput};
+
+pub struct OrbitController {
+    pub focus: Vector3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    pub rotate_sensitivity: f32,
+    pub pan_sensitivity: f32,
+    pub zoom_sensitivity: f32,
+    pub key_speed: f32,
+    pub damping: f32,
+    target_focus: Vector3,
+    target_yaw: f32,
+    target_pitch: f32,
+    target_distance: f32,
+}
+
+impl OrbitController {
+    pub fn new(focus: Vector3, yaw: f32, pitch: f32, distance: f32) -> OrbitController {
+        OrbitController {
+            focus,
+            yaw,
+            pitch,
+            distance,
+            min_distance: 1.0,
+            max_distance: 100.0,
+            min_pitch: -PI / 2.0 + 0.05,
+            max_pitch: -0.05,
+            rotate_sensitivity: 0.005,
+            pan_sensitivity: 0.0015,
+            zoom_sensitivity: 0.002,
+            key_speed: 600.0,
+            damping: 12.0,
+            target_focus: focus,
+            target_yaw: yaw,
+            target_pitch: pitch,
+            target_distance: distance,
+        }
+    }
+
+    pub fn from_camera(camera: &Camera, distance: f32) -> OrbitController {
+        let mut controller = OrbitController::new(camera.position, 0.0, 0.0, distance);
+        controller.sync(camera);
+        controller
+    }
+
+    // Keeps the current distance and orbits around whatever point the camera is looking at
+    pub fn sync(&mut self, camera: &Camera) {
+        let (yaw, pitch) = angles_from_direction(&camera.direction);
+        self.yaw = yaw;
+        self.pitch = pitch.clamp(self.min_pitch, self.max_pitch);
+        self.focus = camera.position + direction_from_angles(yaw, pitch) * self.distance;
+        self.target_yaw = self.yaw;
+        self.target_pitch = self.pitch;
+        self.target_focus = self.focus;
+        self.target_distance = self.distance;
+    }
+
+    pub fn handle_input(&mut self, input: &ControllerInput, delta_time: f32) {
+        if !input.pointer_over_ui {
+            if input.primary_down {
+                self.orbit(input.pointer_delta.0, input.pointer_delta.1);
+            } else if input.secondary_down || input.middle_down {
+                self.pan(input.pointer_delta.0, input.pointer_delta.1);
+            }
+            self.zoom(input.scroll);
+        }
+
+        let key_speed = self.key_speed * delta_time;
+        self.pan(-input.move_axis.x * key_speed, input.move_axis.z * key_speed);
+        self.orbit(input.rotate_axis * key_speed, 0.0);
+    }
+
+    pub fn orbit(&mut self, delta_x: f32, delta_y: f32) {
+        self.target_yaw -= delta_x * self.rotate_sensitivity;
+        self.target_pitch = (self.target_pitch - delta_y * self.rotate_sensitivity)
+            .clamp(self.min_pitch, self.max_pitch);
+    }
+
+    pub fn pan(&mut self, delta_x: f32, delta_y: f32) {
+        let scale = self.target_distance * self.pan_sensitivity;
+        let right = Vector3::new(self.target_yaw.sin(), 0.0, -self.target_yaw.cos());
+        let forward = Vector3::new(self.target_yaw.cos(), 0.0, self.target_yaw.sin());
+        self.target_focus -= right * (delta_x * scale);
+        self.target_focus += forward * (delta_y * scale);
+    }
+
+    pub fn zoom(&mut self, scroll: f32) {
+        self.target_distance = (self.target_distance * (-scroll * self.zoom_sensitivity).exp())
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    pub fn set_focus(&mut self, focus: Vector3) {
+        self.target_focus = focus;
+    }
+
+    pub fn set_angles(&mut self, yaw: f32, pitch: f32) {
+        self.target_yaw = yaw;
+        self.target_pitch = pitch.clamp(self.min_pitch, self.max_pitch);
+    }
+
+    pub fn frame(&mut self, center: Vector3, distance: f32) {
+        self.target_focus = center;
+        self.target_distance = distance.clamp(self.min_distance, self.max_distance);
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, delta_time: f32) {
+        let t = smoothing_factor(self.damping, delta_time);
+        self.yaw = lerp(self.yaw, self.target_yaw, t);
+        self.pitch = lerp(self.pitch, self.target_pitch, t);
+        self.distance = lerp(self.distance, self.target_distance, t);
+        self.focus = lerp_vector(self.focus, self.target_focus, t);
+
+        let direction = direction_from_angles(self.yaw, self.pitch);
+        camera.direction = direction;
+        camera.position = self.focus - direction * self.distance;
+        camera.recalculate();
+    }
+}
@@ -0,0 +1,143 @@
+use std::f32::consts::PI;
+use hexgen_common::vector3::Vector3;
+use crate::camera::Camera;
+use crate::camera::controller::{angles_from_direction, direction_from_angles, lerp, lerp_vector, smoothing_factor, ControllerInput};
+
+// Hex grids only have six facings, so the camera rotates in 60 degree steps
+pub const ROTATION_STEP: f32 = PI / 3.0;
+
+pub struct RtsController {
+    pub focus: Vector3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub pan_speed: f32,
+    pub edge_margin: f32,
+    pub edge_panning: bool,
+    pub zoom_sensitivity: f32,
+    pub damping: f32,
+    bounds: Option<(Vector3, Vector3)>,
+    target_focus: Vector3,
+    target_yaw: f32,
+    target_distance: f32,
+}
+
+impl RtsController {
+    pub fn new(focus: Vector3, yaw: f32, distance: f32) -> RtsController {
+        RtsController {
+            focus,
+            yaw,
+            pitch: -PI / 3.5,
+            distance,
+            min_distance: 2.0,
+            max_distance: 60.0,
+            pan_speed: 1.2,
+            edge_margin: 12.0,
+            edge_panning: true,
+            zoom_sensitivity: 0.002,
+            damping: 10.0,
+            bounds: None,
+            target_focus: focus,
+            target_yaw: yaw,
+            target_distance: distance,
+        }
+    }
+
+    pub fn from_camera(camera: &Camera, distance: f32) -> RtsController {
+        let mut controller = RtsController::new(camera.position, 0.0, distance);
+        controller.sync(camera);
+        controller
+    }
+
+    // Snaps the camera yaw to the nearest hex facing and drops the focus onto the ground plane
+    pub fn sync(&mut self, camera: &Camera) {
+        let (yaw, pitch) = angles_from_direction(&camera.direction);
+        let direction = direction_from_angles(yaw, pitch);
+        let ground_distance = if direction.y < -0.01 { -camera.position.y / direction.y } else { self.distance };
+        let focus = camera.position + direction * ground_distance;
+
+        self.yaw = yaw;
+        self.focus = self.clamp_to_bounds(Vector3::new(focus.x, 0.0, focus.z));
+        let offset = camera.position - self.focus;
+        self.distance = offset.length()
+            .clamp(self.min_distance, self.max_distance);
+        self.target_yaw = (yaw / ROTATION_STEP).round() * ROTATION_STEP;
+        self.target_focus = self.focus;
+        self.target_distance = self.distance;
+    }
+
+    pub fn handle_input(&mut self, input: &ControllerInput, delta_time: f32) {
+        let mut pan = (input.move_axis.x, input.move_axis.z);
+        if self.edge_panning && !input.pointer_over_ui {
+            if let Some((x, y)) = input.pointer_position {
+                let (width, height) = input.screen_size;
+                if x <= self.edge_margin {
+                    pan.0 -= 1.0;
+                } else if x >= width - self.edge_margin {
+                    pan.0 += 1.0;
+                }
+                if y <= self.edge_margin {
+                    pan.1 += 1.0;
+                } else if y >= height - self.edge_margin {
+                    pan.1 -= 1.0;
+                }
+            }
+        }
+        let speed = self.pan_speed * self.target_distance * delta_time * if input.boost { 2.5 } else { 1.0 };
+        self.pan(pan.0 * speed, pan.1 * speed);
+
+        if !input.pointer_over_ui {
+            self.zoom(input.scroll);
+        }
+        if input.rotate_steps != 0 {
+            self.rotate(input.rotate_steps);
+        }
+    }
+
+    pub fn pan(&mut self, right_amount: f32, forward_amount: f32) {
+        let right = Vector3::new(self.target_yaw.sin(), 0.0, -self.target_yaw.cos());
+        let forward = Vector3::new(self.target_yaw.cos(), 0.0, self.target_yaw.sin());
+        let focus = self.target_focus + right * right_amount + forward * forward_amount;
+        self.target_focus = self.clamp_to_bounds(focus);
+    }
+
+    pub fn rotate(&mut self, steps: i32) {
+        self.target_yaw += steps as f32 * ROTATION_STEP;
+    }
+
+    pub fn zoom(&mut self, scroll: f32) {
+        self.target_distance = (self.target_distance * (-scroll * self.zoom_sensitivity).exp())
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    pub fn set_bounds(&mut self, min: Vector3, max: Vector3) {
+        self.bounds = Some((min, max));
+        self.target_focus = self.clamp_to_bounds(self.target_focus);
+    }
+
+    pub fn frame(&mut self, center: Vector3, distance: f32) {
+        self.target_focus = self.clamp_to_bounds(center);
+        self.target_distance = distance.clamp(self.min_distance, self.max_distance);
+    }
+
+    fn clamp_to_bounds(&self, focus: Vector3) -> Vector3 {
+        match self.bounds {
+            Some((min, max)) => Vector3::new(focus.x.clamp(min.x, max.x), focus.y.clamp(min.y, max.y), focus.z.clamp(min.z, max.z)),
+            None => focus,
+        }
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, delta_time: f32) {
+        let t = smoothing_factor(self.damping, delta_time);
+        self.yaw = lerp(self.yaw, self.target_yaw, t);
+        self.distance = lerp(self.distance, self.target_distance, t);
+        self.focus = lerp_vector(self.focus, self.target_focus, t);
+
+        let direction = direction_from_angles(self.yaw, self.pitch);
+        camera.direction = direction;
+        camera.position = self.focus - direction * self.distance;
+        camera.recalculate();
+    }
+}
@@ -0,0 +1,89 @@
+use tracing::info;
+use hexgen_common::matrix::Matrix;
+use crate::camera::perspective::Perspective;
+use crate::camera::projection::{Projection, ProjectionKind};
+
+pub struct Orthographic {
+    // Field of view the view size is matched to, so switching from perspective keeps the zoom level
+    fov: f32,
+    z_far: f32,
+    z_near: f32,
+    // Half of the visible height in world units
+    size: f32,
+    pub orthographic_matrix: Matrix,
+    width: f32,
+    height: f32,
+}
+
+impl Orthographic {
+    pub fn new(fov: f32, z_far: f32, z_near: f32, width: f32, height: f32) -> Orthographic {
+        let size = 5.0;
+        Orthographic {
+            fov,
+            z_far,
+            z_near,
+            size,
+            orthographic_matrix: Orthographic::calculate_matrix(size, z_near, z_far, width, height),
+            width,
+            height,
+        }
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: f32) {
+        self.size = size;
+        self.orthographic_matrix = Orthographic::calculate_matrix(self.size, self.z_near, self.z_far, self.width, self.height);
+    }
+
+    fn calculate_matrix(size: f32, z_near: f32, z_far: f32, width: f32, height: f32) -> Matrix {
+        // size is half the visible height
+        let half_width = size * width / height;
+        Matrix::orthographic(-half_width, half_width, -size, size, z_near, z_far)
+    }
+}
+
+impl Projection for Orthographic {
+    fn kind(&self) -> ProjectionKind {
+        ProjectionKind::Orthographic
+    }
+
+    fn matrix(&self) -> &Matrix {
+        &self.orthographic_matrix
+    }
+
+    fn update(&mut self, width: f32, height: f32) {
+        self.orthographic_matrix = Orthographic::calculate_matrix(self.size, self.z_near, self.z_far, width, height);
+        self.width = width;
+        self.height = height;
+        info!("Updated orthographic matrix");
+    }
+
+    fn viewport(&self) -> (f32, f32) {
+        (self.width, self.height)
+    }
+
+    fn framing_distance(&self, radius: f32) -> f32 {
+        radius / (self.fov / 2.0).tan()
+    }
+
+    fn set_view_distance(&mut self, distance: f32) {
+        let size = distance * (self.fov / 2.0).tan();
+        if (size - self.size).abs() > f32::EPSILON {
+            self.set_size(size);
+        }
+    }
+
+    fn convert(&self, kind: ProjectionKind) -> Box<dyn Projection> {
+        match kind {
+            ProjectionKind::Perspective => Box::new(Perspective::new(self.fov, self.z_far, self.z_near, self.width, self.height)),
+            ProjectionKind::Orthographic => {
+                let mut orthographic = Orthographic::new(self.fov, self.z_far, self.z_near, self.width, self.height);
+                orthographic.set_size(self.size);
+                Box::new(orthographic)
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use tracing::info;
 use hexgen_common::matrix::Matrix;
+use crate::camera::orthographic::Orthographic;
+use crate::camera::projection::{Projection, ProjectionKind};
 
 pub struct Perspective {
     fov: f32,
@@ -22,22 +24,43 @@ impl Perspective {
         }
     }
 
-    pub fn update(&mut self, width: f32, height: f32) {
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    fn calculate_matrix(fov: f32, z_near: f32, z_far: f32, width: f32, height: f32) -> Matrix {
+        Matrix::perspective(fov, width / height, z_near, z_far)
+    }
+}
+
+impl Projection for Perspective {
+    fn kind(&self) -> ProjectionKind {
+        ProjectionKind::Perspective
+    }
+
+    fn matrix(&self) -> &Matrix {
+        &self.perspective_matrix
+    }
+
+    fn update(&mut self, width: f32, height: f32) {
         self.perspective_matrix = Perspective::calculate_matrix(self.fov, self.z_near, self.z_far, width, height);
         self.width = width;
         self.height = height;
         info!("Updated perspective matrix");
     }
 
-    fn calculate_matrix(fov: f32, z_near: f32, z_far: f32, width: f32, height: f32) -> Matrix {
-        let aspect_ratio = height / width;
-        let f = 1.0 / (fov / 2.0).tan();
+    fn viewport(&self) -> (f32, f32) {
+        (self.width, self.height)
+    }
 
-        Matrix([
-            [f * aspect_ratio, 0.0, 0.0, 0.0],
-            [0.0, f, 0.0, 0.0],
-            [0.0, 0.0, (z_far + z_near) / (z_far - z_near), 1.0],
-            [0.0, 0.0, -(2.0 * z_far * z_near) / (z_far - z_near), 0.0],
-        ])
+    fn framing_distance(&self, radius: f32) -> f32 {
+        radius / (self.fov / 2.0).sin()
     }
-}
\ No newline at end of file
+
+    fn convert(&self, kind: ProjectionKind) -> Box<dyn Projection> {
+        match kind {
+            ProjectionKind::Perspective => Box::new(Perspective::new(self.fov, self.z_far, self.z_near, self.width, self.height)),
+            ProjectionKind::Orthographic => Box::new(Orthographic::new(self.fov, self.z_far, self.z_near, self.width, self.height)),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use hexgen_common::matrix::Matrix;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ProjectionKind {
+    Perspective,
+    Orthographic,
+}
+
+pub trait Projection {
+    fn kind(&self) -> ProjectionKind;
+
+    fn matrix(&self) -> &Matrix;
+
+    fn update(&mut self, width: f32, height: f32);
+
+    fn viewport(&self) -> (f32, f32);
+
+    // Distance from which a sphere of the given radius fills the view
+    fn framing_distance(&self, radius: f32) -> f32;
+
+    // Lets projections without perspective foreshortening follow the camera zoom
+    fn set_view_distance(&mut self, _distance: f32) {}
+
+    // Builds a projection of another kind that keeps the field of view and clipping planes
+    fn convert(&self, kind: ProjectionKind) -> Box<dyn Projection>;
+}
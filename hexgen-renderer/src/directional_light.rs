@@ -2,12 +2,20 @@ use hexgen_common::vector3::Vector3;
 
 pub struct DirectionalLight {
     pub direction: Vector3,
+    pub color: Vector3,
+    pub intensity: f32,
 }
 
 impl DirectionalLight {
     pub fn new(direction: Vector3) -> DirectionalLight {
         DirectionalLight {
             direction,
+            color: Vector3::one(),
+            intensity: 1.0,
         }
     }
-}
\ No newline at end of file
+
+    pub fn radiance(&self) -> Vector3 {
+        self.color * self.intensity
+    }
+}
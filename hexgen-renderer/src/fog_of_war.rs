@@ -0,0 +1,135 @@
+use glium::glutin::surface::WindowSurface;
+use glium::texture::RawImage2d;
+use glium::{Display, Rect, Texture2d};
+use tracing::error;
+use hexgen_common::grid::Grid;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CellVisibility {
+    // Never seen, drawn black
+    Unexplored,
+    // Seen before but not right now, drawn darkened and desaturated
+    Explored,
+    Visible,
+}
+
+impl CellVisibility {
+    // Value in the mask texture, fog_of_war.glsl tells the states apart by it
+    fn texel(&self) -> u8 {
+        match self {
+            CellVisibility::Unexplored => 0,
+            CellVisibility::Explored => 128,
+            CellVisibility::Visible => 255,
+        }
+    }
+}
+
+// Per-cell visibility of the map. The shaders look up the cell under every fragment in a mask texture,
+// so the mask can change every tick without touching the scene
+pub struct FogOfWar {
+    pub enabled: bool,
+    // Rows of the mask are the map rows, the same layout as TerrainMap
+    cells: Grid<CellVisibility>,
+    texture: Option<Texture2d>,
+    // Cells changed since the texture was last written
+    dirty: bool,
+}
+
+impl Default for FogOfWar {
+    fn default() -> Self {
+        FogOfWar::new()
+    }
+}
+
+impl FogOfWar {
+    pub fn new() -> FogOfWar {
+        FogOfWar {
+            enabled: false,
+            cells: Grid::new(0, 0, CellVisibility::Unexplored),
+            texture: None,
+            dirty: false,
+        }
+    }
+
+    // Starts over with every cell unexplored, for a new map
+    pub fn resize(&mut self, display: &Display<WindowSurface>, width: u8, height: u8) {
+        self.cells = Grid::new(width, height, CellVisibility::Unexplored);
+        self.texture = None;
+        if !self.cells.is_empty() {
+            match Texture2d::empty(display, width as u32, height as u32) {
+                Ok(texture) => self.texture = Some(texture),
+                Err(e) => error!("Couldn't create the fog of war mask: {}", e),
+            }
+        }
+        self.dirty = true;
+    }
+
+    pub fn width(&self) -> u8 {
+        self.cells.width()
+    }
+
+    pub fn height(&self) -> u8 {
+        self.cells.height()
+    }
+
+    // Cells outside the map are never explored
+    pub fn get(&self, x: u8, z: u8) -> CellVisibility {
+        self.cells.get(x, z).copied().unwrap_or(CellVisibility::Unexplored)
+    }
+
+    pub fn set(&mut self, x: u8, z: u8, visibility: CellVisibility) {
+        if let Some(cell) = self.cells.get_mut(x, z) {
+            if *cell != visibility {
+                *cell = visibility;
+                self.dirty = true;
+            }
+        }
+    }
+
+    // Replaces the visible cells with the given ones, cells no longer seen stay explored
+    pub fn update_visible<I: IntoIterator<Item = (u8, u8)>>(&mut self, visible: I) {
+        for cell in self.cells.values_mut() {
+            if *cell == CellVisibility::Visible {
+                *cell = CellVisibility::Explored;
+                self.dirty = true;
+            }
+        }
+        for (x, z) in visible {
+            self.set(x, z, CellVisibility::Visible);
+        }
+    }
+
+    pub fn reveal_all(&mut self) {
+        self.fill(CellVisibility::Visible);
+    }
+
+    pub fn reset(&mut self) {
+        self.fill(CellVisibility::Unexplored);
+    }
+
+    fn fill(&mut self, visibility: CellVisibility) {
+        self.cells.fill(visibility);
+        self.dirty = true;
+    }
+
+    pub fn texture(&self) -> Option<&Texture2d> {
+        self.texture.as_ref()
+    }
+
+    // Writes changed cells into the mask texture, called by the renderer before drawing
+    pub fn upload(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(texture) = &self.texture {
+            let data: Vec<u8> = self.cells.values().iter().flat_map(|cell| {
+                let texel = cell.texel();
+                [texel, texel, texel, 255]
+            }).collect();
+            let (width, height) = (self.cells.width() as u32, self.cells.height() as u32);
+            let image = RawImage2d::from_raw_rgba(data, (width, height));
+            texture.write(Rect { left: 0, bottom: 0, width, height }, image);
+        }
+        self.dirty = false;
+    }
+}
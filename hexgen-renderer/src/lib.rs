@@ -1,3 +1,8 @@
 pub mod renderer;
 pub mod camera;
-pub mod directional_light;
\ No newline at end of file
+pub mod directional_light;
+pub mod atmosphere;
+pub mod sky;
+pub mod time_of_day;
+pub mod light;
+pub mod fog_of_war;
\ No newline at end of file
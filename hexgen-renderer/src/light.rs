@@ -0,0 +1,99 @@
+use glium::implement_uniform_block;
+use hexgen_common::scene::Scene;
+use hexgen_common::vector3::Vector3;
+
+// Has to match MAX_LIGHTS in the terrain shaders
+pub const MAX_LIGHTS: usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LightKind {
+    Point,
+    Spot,
+}
+
+pub struct Light {
+    pub name: String,
+    pub kind: LightKind,
+    pub enabled: bool,
+    // World position, or offset from the game object the light is attached to
+    pub position: Vector3,
+    // Spot lights only, direction the cone points at
+    pub direction: Vector3,
+    pub color: Vector3,
+    pub intensity: f32,
+    // Distance at which the light fades out completely
+    pub range: f32,
+    // Quadratic falloff factor inside of the range
+    pub attenuation: f32,
+    // Spot lights only, half angles of the full intensity cone and of the cone edge in radians
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    // Name of the game object the light follows
+    pub attached_to: Option<String>,
+}
+
+impl Light {
+    pub fn point(name: String, position: Vector3, color: Vector3, intensity: f32, range: f32) -> Light {
+        Light {
+            name,
+            kind: LightKind::Point,
+            enabled: true,
+            position,
+            direction: Vector3::down(),
+            color,
+            intensity,
+            range,
+            attenuation: 1.0,
+            inner_angle: 0.4,
+            outer_angle: 0.6,
+            attached_to: None,
+        }
+    }
+
+    pub fn spot(name: String, position: Vector3, direction: Vector3, color: Vector3, intensity: f32, range: f32) -> Light {
+        Light {
+            kind: LightKind::Spot,
+            direction,
+            ..Light::point(name, position, color, intensity, range)
+        }
+    }
+
+    pub fn attach(&mut self, game_object_name: String, offset: Vector3) {
+        self.attached_to = Some(game_object_name);
+        self.position = offset;
+    }
+
+    pub fn world_position(&self, scene: &Scene) -> Option<Vector3> {
+        match &self.attached_to {
+            None => Some(self.position),
+            Some(name) => scene
+                .find(name)
+                .and_then(|id| scene.world_position(id))
+                .map(|position| position + self.position),
+        }
+    }
+}
+
+// std140 layout of a single light, everything is packed into vec4s to avoid padding surprises
+#[derive(Copy, Clone, Default)]
+#[repr(C)]
+pub struct GpuLight {
+    // xyz - view space position, w - range
+    pub position_range: [f32; 4],
+    // rgb - color premultiplied with intensity, w - 0 for point and 1 for spot lights
+    pub color_kind: [f32; 4],
+    // xyz - view space spot direction, w - cosine of the outer angle
+    pub direction_cone: [f32; 4],
+    // x - cosine of the inner angle, y - attenuation
+    pub params: [f32; 4],
+}
+
+implement_uniform_block!(GpuLight, position_range, color_kind, direction_cone, params);
+
+#[derive(Copy, Clone, Default)]
+#[repr(C)]
+pub struct LightBlock {
+    pub lights: [GpuLight; MAX_LIGHTS],
+}
+
+implement_uniform_block!(LightBlock, lights);
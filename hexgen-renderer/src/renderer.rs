@@ -1,18 +1,64 @@
+use crate::atmosphere::Atmosphere;
 use crate::camera::Camera;
 use crate::directional_light::DirectionalLight;
+use crate::fog_of_war::FogOfWar;
+use crate::light::{GpuLight, Light, LightBlock, LightKind, MAX_LIGHTS};
+use crate::sky::Sky;
 use glium::glutin::surface::WindowSurface;
-use glium::{uniform, Display, DrawParameters, Frame, Surface};
-use hexgen_common::game_object::GameObject;
+use glium::texture::RawImage2d;
+use glium::uniforms::UniformBuffer;
+use glium::{uniform, Display, DrawParameters, Frame, Program, Surface, Texture2d};
+use hexgen_common::ecs::components::{Renderable, SceneNode};
+use hexgen_common::scene::Scene;
+use std::rc::Rc;
+use hexgen_common::material::shader::{Shader, ShaderCache};
 use tracing::{error, info};
 use winit::event::Event;
 use winit::event_loop::ControlFlow;
+use hexgen_common::matrix::Matrix;
 use hexgen_common::vector3::Vector3;
 
 pub struct Renderer<'a> {
     pub camera: Camera,
     pub directional_light: DirectionalLight,
     pub draw_parameters: DrawParameters<'a>,
-    pub background_color: Vector3
+    pub background_color: Vector3,
+    pub ambient_light: Vector3,
+    pub atmosphere: Atmosphere,
+    pub sky: Option<Sky>,
+    pub fog_of_war: FogOfWar,
+    pub lights: Vec<Light>,
+    pub light_buffer: Option<UniformBuffer<LightBlock>>,
+    // Bound in place of missing material textures so every material can use the same shader
+    pub fallback_textures: Option<(Texture2d, Texture2d)>,
+    pub time: f32,
+}
+
+// Per-frame values shared by every object drawn in the scene
+pub struct SceneUniforms<'b> {
+    pub perspective_matrix: [[f32; 4]; 4],
+    pub view_matrix: [[f32; 4]; 4],
+    pub light_dir: [f32; 3],
+    pub light_color: [f32; 3],
+    pub ambient_light: [f32; 3],
+    pub time: f32,
+    pub fog_color: [f32; 3],
+    pub fog_density: f32,
+    pub fog_start: f32,
+    pub fog_height_falloff: f32,
+    pub light_count: i32,
+    pub lights: &'b UniformBuffer<LightBlock>,
+    pub white_texture: &'b Texture2d,
+    pub flat_normal_texture: &'b Texture2d,
+    pub fog_of_war_enabled: bool,
+    pub fog_of_war_map: &'b Texture2d,
+}
+
+// Opaque meshes are drawn first with depth writes, transparent ones afterwards blended back to front
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderPass {
+    Opaque,
+    Transparent,
 }
 
 impl<'a> Renderer<'a> {
@@ -26,25 +72,150 @@ impl<'a> Renderer<'a> {
             camera,
             directional_light,
             draw_parameters,
-            background_color
+            background_color,
+            ambient_light: Vector3::zero(),
+            atmosphere: Atmosphere::new(),
+            sky: None,
+            fog_of_war: FogOfWar::new(),
+            lights: vec![],
+            light_buffer: None,
+            fallback_textures: None,
+            time: 0.0,
+        }
+    }
+
+    pub fn load(&mut self, display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) {
+        shader_cache.set_define("MAX_LIGHTS", &MAX_LIGHTS.to_string());
+        self.sky = match Sky::new(display, shader_cache) {
+            Ok(sky) => Some(sky),
+            Err(e) => {
+                error!("Couldn't load sky, rendering without it: {}", e);
+                None
+            }
+        };
+        self.light_buffer = Some(UniformBuffer::new(display, LightBlock::default()).unwrap());
+        let white = RawImage2d::from_raw_rgba(vec![255u8, 255, 255, 255], (1, 1));
+        let flat_normal = RawImage2d::from_raw_rgba(vec![128u8, 128, 255, 255], (1, 1));
+        self.fallback_textures = Some((Texture2d::new(display, white).unwrap(), Texture2d::new(display, flat_normal).unwrap()));
+    }
+
+    pub fn swap_programs(&mut self, reloaded: &[(Shader, Rc<Program>)]) {
+        if let Some(sky) = &mut self.sky {
+            sky.swap_program(reloaded);
         }
     }
 
+    // Packs the closest enabled lights into the uniform block, returns how many were written
+    fn upload_lights(&self, light_buffer: &UniformBuffer<LightBlock>, scene: &Scene, view_matrix: &[[f32; 4]; 4]) -> i32 {
+        let camera_position = self.camera.position;
+        let mut visible: Vec<(f32, &Light, Vector3)> = self.lights.iter()
+            .filter(|light| light.enabled)
+            .filter_map(|light| light.world_position(scene).map(|position| {
+                let offset = position - camera_position;
+                (offset.x * offset.x + offset.y * offset.y + offset.z * offset.z, light, position)
+            }))
+            .collect();
+        visible.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut block = LightBlock::default();
+        for (gpu_light, (_, light, position)) in block.lights.iter_mut().zip(visible.iter()) {
+            let view_position = view_point(view_matrix, (*position).into());
+            let view_dir = view_direction(view_matrix, light.direction.into());
+            let color = light.color * light.intensity;
+            *gpu_light = GpuLight {
+                position_range: [view_position[0], view_position[1], view_position[2], light.range],
+                color_kind: [color.x, color.y, color.z, if light.kind == LightKind::Spot { 1.0 } else { 0.0 }],
+                direction_cone: [view_dir[0], view_dir[1], view_dir[2], light.outer_angle.cos()],
+                params: [light.inner_angle.cos(), light.attenuation, 0.0, 0.0],
+            };
+        }
+        light_buffer.write(&block);
+        visible.len().min(MAX_LIGHTS) as i32
+    }
+
     pub fn render(
         &mut self,
-        game_objects: &mut Vec<GameObject>,
-        mut frame: &mut Frame,
+        scene: &mut Scene,
+        frame: &mut Frame,
     ) {
+        scene.update_world_matrices();
         frame.clear_color_and_depth((self.background_color.x, self.background_color.y, self.background_color.z, 1.0), 1.0);
-        let light_dir: [f32; 3] = self.directional_light.direction.into();
-        for go in game_objects.iter_mut() {
-            go.render(
-                go.model_matrix.0.clone(),
-                self.camera.perspective.perspective_matrix.0.clone(),
-                self.camera.view_matrix.0.clone(),
-                light_dir,
-                &mut frame,
+
+        let perspective_matrix = self.camera.projection.matrix().0;
+        let view_matrix = self.camera.view_matrix.0;
+        let sun_dir: [f32; 3] = self.directional_light.direction.into();
+        if let Some(sky) = &self.sky {
+            if self.atmosphere.sky_enabled {
+                sky.render(&self.atmosphere, perspective_matrix, view_matrix, sun_dir, frame);
+            }
+        }
+
+        self.fog_of_war.upload();
+        let (light_buffer, (white_texture, flat_normal_texture)) = match (&self.light_buffer, &self.fallback_textures) {
+            (Some(light_buffer), Some(fallback_textures)) => (light_buffer, fallback_textures),
+            _ => {
+                error!("Renderer resources are not loaded");
+                return;
+            }
+        };
+        let light_count = self.upload_lights(light_buffer, scene, &view_matrix);
+
+        let uniforms = SceneUniforms {
+            perspective_matrix,
+            view_matrix,
+            light_dir: view_direction(&view_matrix, sun_dir),
+            light_color: self.directional_light.radiance().into(),
+            ambient_light: self.ambient_light.into(),
+            time: self.time,
+            fog_color: self.atmosphere.fog_color.into(),
+            fog_density: self.atmosphere.effective_fog_density(),
+            fog_start: self.atmosphere.fog_start,
+            fog_height_falloff: self.atmosphere.fog_height_falloff,
+            light_count,
+            lights: light_buffer,
+            white_texture,
+            flat_normal_texture,
+            // Without a mask texture the white fallback is bound, the shaders skip the lookup anyway
+            fog_of_war_enabled: self.fog_of_war.enabled && self.fog_of_war.texture().is_some(),
+            fog_of_war_map: self.fog_of_war.texture().unwrap_or(white_texture),
+        };
+        // Render system, draws every renderable entity at its scene node's world matrix
+        let renderables: Vec<(&Renderable, &Matrix)> = scene.world.query2::<Renderable, SceneNode>()
+            .filter_map(|(_, renderable, node)| scene.world_matrix(node.0).map(|world_matrix| (renderable, world_matrix)))
+            .collect();
+        for (renderable, world_matrix) in renderables.iter() {
+            renderable.render(
+                world_matrix.0,
+                &uniforms,
+                frame,
                 &self.draw_parameters,
+                RenderPass::Opaque,
+            );
+        }
+
+        let camera_position = self.camera.position;
+        let mut transparent: Vec<(f32, &Renderable, &Matrix)> = renderables.iter()
+            .filter(|(renderable, _)| renderable.model.borrow().has_transparent_meshes())
+            .map(|(renderable, world_matrix)| {
+                let position = Vector3::new(world_matrix.0[3][0], world_matrix.0[3][1], world_matrix.0[3][2]);
+                ((position - camera_position).length_squared(), *renderable, *world_matrix)
+            })
+            .collect();
+        if transparent.is_empty() {
+            return;
+        }
+        transparent.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut blend_parameters = self.draw_parameters.clone();
+        blend_parameters.blend = glium::Blend::alpha_blending();
+        blend_parameters.depth.write = false;
+        for (_, renderable, world_matrix) in transparent {
+            renderable.render(
+                world_matrix.0,
+                &uniforms,
+                frame,
+                &blend_parameters,
+                RenderPass::Transparent,
             );
         }
     }
@@ -65,7 +236,7 @@ impl<'a> Renderer<'a> {
                     info!("Window resized event triggered");
                     display.resize((window_size.width, window_size.height));
                     self.camera
-                        .perspective
+                        .projection
                         .update(window_size.width as f32, window_size.height as f32);
                 }
                 _ => (),
@@ -75,57 +246,95 @@ impl<'a> Renderer<'a> {
     }
 }
 
+fn view_point(view_matrix: &[[f32; 4]; 4], point: [f32; 3]) -> [f32; 3] {
+    let direction = view_direction(view_matrix, point);
+    [direction[0] + view_matrix[3][0], direction[1] + view_matrix[3][1], direction[2] + view_matrix[3][2]]
+}
+
+// Rotates a world space direction into view space, the shaders light in view space
+fn view_direction(view_matrix: &[[f32; 4]; 4], direction: [f32; 3]) -> [f32; 3] {
+    let mut result = [0f32; 3];
+    for (row, value) in result.iter_mut().enumerate() {
+        *value = view_matrix[0][row] * direction[0] + view_matrix[1][row] * direction[1] + view_matrix[2][row] * direction[2];
+    }
+    result
+}
+
 pub trait Render {
     fn render(
-        &mut self,
+        &self,
         model_matrix: [[f32; 4]; 4],
-        perspective_matrix: [[f32; 4]; 4],
-        view_matrix: [[f32; 4]; 4],
-        light_dir: [f32; 3],
+        scene: &SceneUniforms,
         frame: &mut Frame,
         draw_parameters: &DrawParameters,
+        pass: RenderPass,
     );
 }
 
-impl<'a> Render for GameObject {
+impl Render for Renderable {
     fn render(
-        &mut self,
+        &self,
         model_matrix: [[f32; 4]; 4],
-        perspective_matrix: [[f32; 4]; 4],
-        view_matrix: [[f32; 4]; 4],
-        light_dir: [f32; 3],
+        scene: &SceneUniforms,
         frame: &mut Frame,
         draw_parameters: &DrawParameters,
+        pass: RenderPass,
     )
     {
         let model_rc = self.model.clone();
         let model = model_rc.borrow();
         for mesh in model.meshes.iter() {
             if let Some(material) = &mesh.material {
+                if material.is_transparent() != (pass == RenderPass::Transparent) {
+                    continue;
+                }
                 if let Some(program) = &material.program {
                     let ambient_color: [f32; 3] = material.ambient_color.into();
                     let diffuse_color: [f32; 3] = material.diffuse_color.into();
                     let specular_color: [f32; 3] = material.specular_color.into();
+                    let emissive_color: [f32; 3] = material.emissive_color.into();
+                    let diffuse_map = material.diffuse_texture.as_deref().unwrap_or(scene.white_texture);
+                    let normal_map = material.normal_texture.as_deref().unwrap_or(scene.flat_normal_texture);
                     let uniforms = uniform! {
                 //vertex uniforms
                 model: model_matrix,
-                perspective: perspective_matrix,
-                view: view_matrix,
+                perspective: scene.perspective_matrix,
+                view: scene.view_matrix,
+                time: scene.time,
 
                 //fragment uniforms
-                u_light: light_dir,
+                u_light: scene.light_dir,
+                light_color: scene.light_color,
+                ambient_light: scene.ambient_light,
+                shore_mask: self.shore_mask as i32,
                 ambient_color: ambient_color,
                 diffuse_color: diffuse_color,
                 specular_color: specular_color,
+                emissive_color: emissive_color,
+                shininess: material.shininess,
+                opacity: material.opacity,
+                fog_color: scene.fog_color,
+                fog_density: scene.fog_density,
+                fog_start: scene.fog_start,
+                fog_height_falloff: scene.fog_height_falloff,
+                light_count: scene.light_count,
+                LightBlock: scene.lights,
+                diffuse_map: diffuse_map,
+                normal_map: normal_map,
+                has_normal_map: material.normal_texture.is_some(),
+                fog_of_war_enabled: scene.fog_of_war_enabled,
+                fog_of_war_map: scene.fog_of_war_map,
             };
                     let vertex_buffer = mesh.vertex_buffer.as_ref().unwrap();
                     let normals_buffer = mesh.normals_buffer.as_ref().unwrap();
+                    let tangents_buffer = mesh.tangents_buffer.as_ref().unwrap();
                     let index_buffer = mesh.index_buffer.as_ref().unwrap();
                     frame
                         .draw(
                             (
                                 vertex_buffer,
                                 normals_buffer,
+                                tangents_buffer,
                             ),
                             index_buffer,
                             program,
@@ -141,4 +350,3 @@ impl<'a> Render for GameObject {
         }
     }
 }
-
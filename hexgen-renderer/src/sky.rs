@@ -0,0 +1,85 @@
+use glium::glutin::surface::WindowSurface;
+use glium::{implement_vertex, uniform, Display, DrawParameters, Frame, Program, Surface, VertexBuffer};
+use glium::index::{NoIndices, PrimitiveType};
+use std::rc::Rc;
+use tracing::info;
+use hexgen_common::error::{Error, Result};
+use hexgen_common::material::shader::{Shader, ShaderCache};
+use crate::atmosphere::Atmosphere;
+
+#[derive(Copy, Clone)]
+struct SkyVertex {
+    position: (f32, f32),
+}
+
+implement_vertex!(SkyVertex, position);
+
+pub struct Sky {
+    shader: Shader,
+    program: Rc<Program>,
+    vertex_buffer: VertexBuffer<SkyVertex>,
+}
+
+impl Sky {
+    pub fn new(display: &Display<WindowSurface>, shader_cache: &mut ShaderCache) -> Result<Sky> {
+        let shader = Shader::new("./res/shaders/sky.vert", "./res/shaders/sky.frag");
+        let program = shader_cache.load(&shader, display)?;
+        // Single triangle covering the whole screen
+        let vertices = [
+            SkyVertex { position: (-1.0, -1.0) },
+            SkyVertex { position: (3.0, -1.0) },
+            SkyVertex { position: (-1.0, 3.0) },
+        ];
+        let vertex_buffer = VertexBuffer::new(display, &vertices).map_err(|e| Error::Buffer {
+            kind: "sky vertex",
+            message: e.to_string(),
+        })?;
+        info!("Loaded sky");
+
+        Ok(Sky {
+            shader,
+            program,
+            vertex_buffer,
+        })
+    }
+
+    pub fn swap_program(&mut self, reloaded: &[(Shader, Rc<Program>)]) {
+        if let Some((_, program)) = reloaded.iter().find(|(shader, _)| shader == &self.shader) {
+            self.program = program.clone();
+            info!("Reloaded sky program");
+        }
+    }
+
+    pub fn render(
+        &self,
+        atmosphere: &Atmosphere,
+        perspective_matrix: [[f32; 4]; 4],
+        view_matrix: [[f32; 4]; 4],
+        sun_dir: [f32; 3],
+        frame: &mut Frame,
+    ) {
+        let zenith_color: [f32; 3] = atmosphere.zenith_color.into();
+        let horizon_color: [f32; 3] = atmosphere.horizon_color.into();
+        let ground_color: [f32; 3] = atmosphere.ground_color.into();
+        let sun_color: [f32; 3] = atmosphere.sun_color.into();
+        let uniforms = uniform! {
+            perspective: perspective_matrix,
+            view: view_matrix,
+            sun_dir: sun_dir,
+            zenith_color: zenith_color,
+            horizon_color: horizon_color,
+            ground_color: ground_color,
+            sun_color: sun_color,
+            sun_size: atmosphere.sun_size,
+        };
+        frame
+            .draw(
+                &self.vertex_buffer,
+                NoIndices(PrimitiveType::TrianglesList),
+                &self.program,
+                &uniforms,
+                &DrawParameters::default(),
+            )
+            .unwrap();
+    }
+}
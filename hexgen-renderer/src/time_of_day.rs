@@ -0,0 +1,184 @@
+use std::f32::consts::PI;
+use hexgen_common::vector3::Vector3;
+use crate::renderer::Renderer;
+
+pub const HOURS_PER_DAY: f32 = 24.0;
+
+pub trait Interpolate: Copy {
+    fn interpolate(from: Self, to: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(from: Self, to: Self, t: f32) -> Self {
+        from + (to - from) * t
+    }
+}
+
+impl Interpolate for Vector3 {
+    fn interpolate(from: Self, to: Self, t: f32) -> Self {
+        from + (to - from) * t
+    }
+}
+
+// Values keyed by hour of the day, sampling wraps around midnight
+pub struct Gradient<T: Interpolate> {
+    // Sorted by hour and never empty
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Interpolate> Gradient<T> {
+    // Panics without keys, there would be nothing to sample
+    pub fn new(mut keys: Vec<(f32, T)>) -> Gradient<T> {
+        assert!(!keys.is_empty(), "a gradient needs at least one key");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Gradient {
+            keys,
+        }
+    }
+
+    pub fn keys(&self) -> &[(f32, T)] {
+        &self.keys
+    }
+
+    // Values can be edited in place, the hours stay fixed so the keys stay sorted
+    pub fn values_mut(&mut self) -> impl Iterator<Item = (f32, &mut T)> {
+        self.keys.iter_mut().map(|(hour, value)| (*hour, value))
+    }
+
+    pub fn sample(&self, hour: f32) -> T {
+        let hour = hour.rem_euclid(HOURS_PER_DAY);
+        let next = self.keys.iter().position(|key| key.0 > hour).unwrap_or(0);
+        let previous = if next == 0 { self.keys.len() - 1 } else { next - 1 };
+        let (from_hour, from) = self.keys[previous];
+        let (to_hour, to) = self.keys[next];
+
+        let span = (to_hour - from_hour).rem_euclid(HOURS_PER_DAY);
+        if span == 0.0 {
+            return from;
+        }
+        let t = (hour - from_hour).rem_euclid(HOURS_PER_DAY) / span;
+        T::interpolate(from, to, t)
+    }
+}
+
+pub struct TimeOfDay {
+    pub enabled: bool,
+    pub paused: bool,
+    // Current hour in [0, 24)
+    pub hour: f32,
+    // In-game hours that pass every real second
+    pub speed: f32,
+    // Compass direction the sun rises from, in radians
+    pub sun_azimuth: f32,
+    // Tilt of the sun path away from the zenith, in radians
+    pub sun_tilt: f32,
+    pub light_color: Gradient<Vector3>,
+    pub light_intensity: Gradient<f32>,
+    pub ambient_color: Gradient<Vector3>,
+    pub zenith_color: Gradient<Vector3>,
+    pub horizon_color: Gradient<Vector3>,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        TimeOfDay::new()
+    }
+}
+
+impl TimeOfDay {
+    pub fn new() -> TimeOfDay {
+        TimeOfDay {
+            enabled: false,
+            paused: false,
+            hour: 10.0,
+            speed: 0.5,
+            sun_azimuth: 0.0,
+            sun_tilt: 0.4,
+            light_color: Gradient::new(vec![
+                (0.0, Vector3::new(0.55, 0.65, 0.9)),
+                (5.5, Vector3::new(0.55, 0.6, 0.85)),
+                (6.5, Vector3::new(1.0, 0.6, 0.35)),
+                (9.0, Vector3::new(1.0, 0.95, 0.85)),
+                (12.0, Vector3::new(1.0, 1.0, 0.95)),
+                (16.0, Vector3::new(1.0, 0.93, 0.8)),
+                (18.0, Vector3::new(1.0, 0.5, 0.25)),
+                (19.0, Vector3::new(0.55, 0.6, 0.85)),
+            ]),
+            light_intensity: Gradient::new(vec![
+                (0.0, 0.25),
+                (5.5, 0.05),
+                (6.5, 0.5),
+                (9.0, 1.0),
+                (16.0, 1.0),
+                (18.0, 0.5),
+                (19.0, 0.05),
+                (20.0, 0.25),
+            ]),
+            ambient_color: Gradient::new(vec![
+                (0.0, Vector3::new(0.03, 0.04, 0.08)),
+                (6.0, Vector3::new(0.12, 0.1, 0.1)),
+                (12.0, Vector3::new(0.2, 0.2, 0.22)),
+                (18.0, Vector3::new(0.14, 0.1, 0.1)),
+                (20.0, Vector3::new(0.03, 0.04, 0.08)),
+            ]),
+            zenith_color: Gradient::new(vec![
+                (0.0, Vector3::new(0.01, 0.02, 0.06)),
+                (5.5, Vector3::new(0.05, 0.07, 0.2)),
+                (7.0, Vector3::new(0.2, 0.35, 0.65)),
+                (12.0, Vector3::new(0.18, 0.36, 0.68)),
+                (17.5, Vector3::new(0.2, 0.3, 0.6)),
+                (19.0, Vector3::new(0.06, 0.06, 0.2)),
+                (20.5, Vector3::new(0.01, 0.02, 0.06)),
+            ]),
+            horizon_color: Gradient::new(vec![
+                (0.0, Vector3::new(0.03, 0.05, 0.1)),
+                (5.5, Vector3::new(0.25, 0.2, 0.3)),
+                (6.5, Vector3::new(0.95, 0.6, 0.4)),
+                (9.0, Vector3::new(0.68, 0.8, 0.9)),
+                (16.5, Vector3::new(0.68, 0.8, 0.9)),
+                (18.3, Vector3::new(0.95, 0.5, 0.3)),
+                (19.5, Vector3::new(0.15, 0.12, 0.2)),
+                (21.0, Vector3::new(0.03, 0.05, 0.1)),
+            ]),
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.paused {
+            return;
+        }
+        self.hour = (self.hour + self.speed * delta_time).rem_euclid(HOURS_PER_DAY);
+    }
+
+    // Direction towards the sun, the sun rises at 6:00 and sets at 18:00
+    pub fn sun_direction(&self) -> Vector3 {
+        let angle = (self.hour - 6.0) / HOURS_PER_DAY * 2.0 * PI;
+        let horizontal = Vector3::new(self.sun_azimuth.cos(), 0.0, self.sun_azimuth.sin());
+        let side = Vector3::new(-self.sun_azimuth.sin(), 0.0, self.sun_azimuth.cos());
+        let arc = Vector3::up() * self.sun_tilt.cos() + side * self.sun_tilt.sin();
+        horizontal * angle.cos() + arc * angle.sin()
+    }
+
+    pub fn moon_direction(&self) -> Vector3 {
+        self.sun_direction() * -1.0
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.sun_direction().y < 0.0
+    }
+
+    pub fn apply(&self, renderer: &mut Renderer) {
+        let light = &mut renderer.directional_light;
+        light.direction = if self.is_night() { self.moon_direction() } else { self.sun_direction() };
+        light.color = self.light_color.sample(self.hour);
+        light.intensity = self.light_intensity.sample(self.hour);
+
+        renderer.ambient_light = self.ambient_color.sample(self.hour);
+
+        let atmosphere = &mut renderer.atmosphere;
+        atmosphere.zenith_color = self.zenith_color.sample(self.hour);
+        atmosphere.horizon_color = self.horizon_color.sample(self.hour);
+        atmosphere.fog_color = atmosphere.horizon_color;
+        atmosphere.sun_color = light.color;
+    }
+}
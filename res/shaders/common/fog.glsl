@@ -0,0 +1,14 @@
+#version 140
+
+uniform vec3 fog_color;
+uniform float fog_density;
+uniform float fog_start;
+uniform float fog_height_falloff;
+
+// Exponential fog that thins out with height
+vec3 apply_fog(vec3 lit_color, vec3 view_position, float world_height) {
+    float fog_distance = max(length(view_position) - fog_start, 0.0);
+    float density = fog_density * exp(-fog_height_falloff * max(world_height, 0.0));
+    float fog = 1.0 - exp(-fog_distance * density);
+    return mix(lit_color, fog_color, clamp(fog, 0.0, 1.0));
+}
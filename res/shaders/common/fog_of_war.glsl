@@ -0,0 +1,46 @@
+#version 140
+
+uniform bool fog_of_war_enabled;
+// One texel per map cell, 0 unexplored, 0.5 explored, 1 visible
+uniform sampler2D fog_of_war_map;
+
+// Map cell with the closest centre, the inverse of tile_center in the generator.
+// Mirrors cell_at in hexgen-generator/src/map.rs, keep the two in sync.
+// Columns run along world Z 1 / 1.2 apart, odd columns are shifted half a tile along X
+ivec2 fog_of_war_cell(vec2 world) {
+    int column = int(round(world.y * 1.2));
+    ivec2 closest = ivec2(column, 0);
+    float closest_distance = 1e9;
+    for (int x = column - 1; x <= column + 1; x++) {
+        float shift = float(x & 1) * 0.5;
+        int z = int(round(world.x - shift));
+        vec2 offset = world - vec2(float(z) + shift, float(x) / 1.2);
+        float distance_squared = dot(offset, offset);
+        if (distance_squared < closest_distance) {
+            closest_distance = distance_squared;
+            closest = ivec2(x, z);
+        }
+    }
+    return closest;
+}
+
+// Black where the map was never seen, darkened and grey where it was seen before
+vec3 apply_fog_of_war(vec3 lit_color, vec3 world_position) {
+    if (!fog_of_war_enabled) {
+        return lit_color;
+    }
+    ivec2 cell = fog_of_war_cell(world_position.xz);
+    ivec2 size = textureSize(fog_of_war_map, 0);
+    if (any(lessThan(cell, ivec2(0))) || any(greaterThanEqual(cell, size))) {
+        return vec3(0.0);
+    }
+    float state = texelFetch(fog_of_war_map, cell, 0).r;
+    if (state < 0.25) {
+        return vec3(0.0);
+    }
+    if (state < 0.75) {
+        float grey = dot(lit_color, vec3(0.299, 0.587, 0.114));
+        return mix(lit_color, vec3(grey), 0.8) * 0.45;
+    }
+    return lit_color;
+}
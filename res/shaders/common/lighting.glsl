@@ -0,0 +1,46 @@
+#version 140
+
+// MAX_LIGHTS is injected by the renderer to match the size of its light uniform block
+#ifndef MAX_LIGHTS
+#define MAX_LIGHTS 8
+#endif
+
+struct Light {
+    vec4 position_range;
+    vec4 color_kind;
+    vec4 direction_cone;
+    vec4 params;
+};
+
+layout(std140) uniform LightBlock {
+    Light lights[MAX_LIGHTS];
+};
+uniform int light_count;
+
+// Point and spot lights, positions and directions are in view space
+vec3 local_lights(vec3 position, vec3 normal, vec3 diffuse_albedo, vec3 specular_albedo, float exponent) {
+    vec3 result = vec3(0.0);
+    vec3 view_dir = normalize(-position);
+    for (int i = 0; i < light_count; i++) {
+        vec3 to_light = lights[i].position_range.xyz - position;
+        float range = lights[i].position_range.w;
+        float light_distance = length(to_light);
+        if (light_distance >= range) {
+            continue;
+        }
+
+        vec3 light_dir = to_light / light_distance;
+        float window = pow(clamp(1.0 - pow(light_distance / range, 4.0), 0.0, 1.0), 2.0);
+        float attenuation = window / (1.0 + lights[i].params.y * light_distance * light_distance);
+        if (lights[i].color_kind.w > 0.5) {
+            float cone = dot(-light_dir, normalize(lights[i].direction_cone.xyz));
+            attenuation *= smoothstep(lights[i].direction_cone.w, lights[i].params.x, cone);
+        }
+
+        float diffuse = max(dot(normal, light_dir), 0.0);
+        vec3 half_direction = normalize(light_dir + view_dir);
+        float specular = pow(max(dot(half_direction, normal), 0.0), exponent);
+        result += lights[i].color_kind.rgb * attenuation * (diffuse * diffuse_albedo + specular * specular_albedo);
+    }
+    return result;
+}
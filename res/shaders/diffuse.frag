@@ -2,9 +2,15 @@
 
 in vec3 v_normal;
 in vec3 v_position;
+in vec3 v_view_position;
+in vec3 v_world_position;
+in vec2 v_tex_coords;
+in vec3 v_tangent;
 
 out vec4 color;
 uniform vec3 u_light;
+uniform vec3 light_color;
+uniform vec3 ambient_light;
 
 //const vec3 ambient_color = vec3(0.2, 0.0, 0.0);
 //const vec3 diffuse_color = vec3(0.6, 0.0, 0.0);
@@ -12,13 +18,40 @@ uniform vec3 u_light;
 uniform vec3 ambient_color;
 uniform vec3 diffuse_color;
 uniform vec3 specular_color;
+uniform vec3 emissive_color;
+uniform float shininess;
+uniform float opacity;
+uniform sampler2D diffuse_map;
+uniform sampler2D normal_map;
+uniform bool has_normal_map;
+
+#include "common/lighting.glsl"
+#include "common/fog.glsl"
+#include "common/fog_of_war.glsl"
+
+// Tangent space normal from the normal map, or the interpolated vertex normal without one
+vec3 surface_normal() {
+    vec3 normal = normalize(v_normal);
+    if (!has_normal_map) {
+        return normal;
+    }
+    vec3 tangent = normalize(v_tangent - dot(v_tangent, normal) * normal);
+    vec3 bitangent = cross(normal, tangent);
+    vec3 mapped = texture(normal_map, v_tex_coords).xyz * 2.0 - 1.0;
+    return normalize(mat3(tangent, bitangent, normal) * mapped);
+}
 
 void main() {
-    float diffuse = max(dot(normalize(v_normal), normalize(u_light)), 0.0);
+    vec3 normal = surface_normal();
+    vec3 albedo = diffuse_color * texture(diffuse_map, v_tex_coords).rgb;
+    float diffuse = max(dot(normal, normalize(u_light)), 0.0);
 
     vec3 camera_dir = normalize(-v_position);
     vec3 half_direction = normalize(normalize(u_light) + camera_dir);
-    float specular = pow(max(dot(half_direction, normalize(v_normal)), 0.0), 16.0);
+    float specular = pow(max(dot(half_direction, normal), 0.0), shininess);
 
-    color = vec4(ambient_color + diffuse * diffuse_color + specular * specular_color, 1.0);
+    vec3 lit = ambient_color + ambient_light * albedo + light_color * (diffuse * albedo + specular * specular_color);
+    lit += local_lights(v_view_position, normal, albedo, specular_color, shininess);
+    lit += emissive_color;
+    color = vec4(apply_fog_of_war(apply_fog(lit, v_view_position, v_world_position.y), v_world_position), opacity);
 }
\ No newline at end of file
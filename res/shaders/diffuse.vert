@@ -2,9 +2,15 @@
 
 in vec3 position;
 in vec3 normal;
+in vec2 tex_coords;
+in vec3 tangent;
 
 out vec3 v_normal;
 out vec3 v_position;
+out vec3 v_view_position;
+out vec3 v_world_position;
+out vec2 v_tex_coords;
+out vec3 v_tangent;
 
 uniform mat4 perspective;
 uniform mat4 model;
@@ -15,4 +21,8 @@ void main() {
     v_normal = transpose(inverse(mat3(modelview))) * normal;
     gl_Position = perspective * modelview * vec4(position, 1.0);
     v_position = gl_Position.xyz / gl_Position.w;
-}
\ No newline at end of file
+    v_view_position = (modelview * vec4(position, 1.0)).xyz;
+    v_world_position = (model * vec4(position, 1.0)).xyz;
+    v_tex_coords = tex_coords;
+    v_tangent = mat3(modelview) * tangent;
+}
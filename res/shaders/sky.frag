@@ -0,0 +1,28 @@
+#version 140
+
+in vec4 v_near;
+in vec4 v_far;
+
+out vec4 color;
+
+uniform vec3 sun_dir;
+uniform vec3 zenith_color;
+uniform vec3 horizon_color;
+uniform vec3 ground_color;
+uniform vec3 sun_color;
+uniform float sun_size;
+
+void main() {
+    vec3 direction = normalize(v_far.xyz / v_far.w - v_near.xyz / v_near.w);
+    vec3 sky;
+    if (direction.y >= 0.0) {
+        sky = mix(horizon_color, zenith_color, pow(direction.y, 0.5));
+    } else {
+        sky = mix(horizon_color, ground_color, pow(-direction.y, 0.35));
+    }
+
+    float sun_angle = dot(direction, normalize(sun_dir));
+    float disk = smoothstep(cos(sun_size), cos(sun_size * 0.8), sun_angle);
+    float glow = pow(max(sun_angle, 0.0), 64.0) * 0.35;
+    color = vec4(sky + sun_color * (disk + glow), 1.0);
+}
@@ -0,0 +1,17 @@
+#version 150
+
+in vec2 position;
+
+out vec4 v_near;
+out vec4 v_far;
+
+uniform mat4 perspective;
+uniform mat4 view;
+
+void main() {
+    // Only the camera rotation matters for the sky, so the translation is dropped
+    mat4 inverse_view_projection = inverse(perspective * mat4(mat3(view)));
+    v_near = inverse_view_projection * vec4(position, -1.0, 1.0);
+    v_far = inverse_view_projection * vec4(position, 1.0, 1.0);
+    gl_Position = vec4(position, 1.0, 1.0);
+}
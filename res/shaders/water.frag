@@ -0,0 +1,81 @@
+#version 140
+
+in vec3 v_normal;
+in vec3 v_position;
+in vec3 v_local_position;
+in vec3 v_world_position;
+
+out vec4 color;
+
+uniform vec3 u_light;
+uniform vec3 light_color;
+uniform vec3 ambient_light;
+uniform mat4 view;
+uniform float time;
+uniform int shore_mask;
+
+uniform vec3 ambient_color;
+uniform vec3 diffuse_color;
+uniform vec3 specular_color;
+uniform float shininess;
+uniform float opacity;
+
+#include "common/lighting.glsl"
+#include "common/fog.glsl"
+#include "common/fog_of_war.glsl"
+
+const vec3 deep_color = vec3(0.05, 0.22, 0.35);
+const vec3 foam_color = vec3(0.95, 0.97, 1.0);
+const float wave_amplitude = 0.02;
+const float foam_width = 0.07;
+const float hex_apothem = 0.5;
+
+// Gradient of the wave_height function in water.vert
+vec2 wave_gradient(vec2 p) {
+    float dx = 2.1 * cos(p.x * 2.1 + time * 1.3) + 0.3 * 5.0 * cos((p.x + p.y) * 5.0 + time * 2.3);
+    float dz = 0.6 * 3.3 * cos(p.y * 3.3 - time * 1.7) + 0.3 * 5.0 * cos((p.x + p.y) * 5.0 + time * 2.3);
+    return wave_amplitude * vec2(dx, dz);
+}
+
+// Distance to the closest hex edge that borders land, bit i of shore_mask marks the neighbour at i * 60 degrees
+float shore_distance(vec2 p) {
+    float closest = 1.0;
+    for (int i = 0; i < 6; i++) {
+        if ((shore_mask & (1 << i)) != 0) {
+            float angle = radians(60.0 * float(i));
+            vec2 edge_normal = vec2(cos(angle), sin(angle));
+            closest = min(closest, hex_apothem - dot(p, edge_normal));
+        }
+    }
+    return closest;
+}
+
+void main() {
+    vec3 normal = normalize(v_normal);
+    if (v_local_position.y > 0.05) {
+        vec2 gradient = wave_gradient(v_world_position.xz);
+        normal = normalize(mat3(view) * vec3(-gradient.x, 1.0, -gradient.y));
+    }
+
+    vec3 light_dir = normalize(u_light);
+    vec3 view_dir = normalize(-v_position);
+    float facing = max(dot(normal, view_dir), 0.0);
+    float fresnel = 0.02 + 0.98 * pow(1.0 - facing, 5.0);
+
+    vec3 water = mix(deep_color, diffuse_color, 0.35 + 0.65 * fresnel);
+    float diffuse = max(dot(normal, light_dir), 0.0);
+
+    vec3 half_direction = normalize(light_dir + view_dir);
+    float glint = pow(max(dot(half_direction, normal), 0.0), shininess);
+    vec3 specular = glint * (specular_color + vec3(0.8));
+
+    float shore = shore_distance(v_local_position.xz);
+    float foam_edge = foam_width * (0.75 + 0.25 * sin(time * 2.0 + v_world_position.x * 7.0 + v_world_position.z * 5.0));
+    float foam = 1.0 - smoothstep(foam_edge * 0.5, foam_edge, shore);
+
+    vec3 lit = ambient_color + ambient_light * water + light_color * (water * (0.45 + 0.55 * diffuse) + specular);
+    lit += local_lights(v_position, normal, water, specular_color + vec3(0.8), shininess);
+    vec3 foam_lit = foam_color * (ambient_light + light_color * (0.5 + 0.5 * diffuse));
+    vec3 fogged = apply_fog(mix(lit, foam_lit, foam * 0.85), v_position, v_world_position.y);
+    color = vec4(apply_fog_of_war(fogged, v_world_position), opacity);
+}
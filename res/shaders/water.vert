@@ -0,0 +1,35 @@
+#version 150
+
+in vec3 position;
+in vec3 normal;
+
+out vec3 v_normal;
+out vec3 v_position;
+out vec3 v_local_position;
+out vec3 v_world_position;
+
+uniform mat4 perspective;
+uniform mat4 model;
+uniform mat4 view;
+uniform float time;
+
+const float wave_amplitude = 0.02;
+
+float wave_height(vec2 p) {
+    return wave_amplitude * (sin(p.x * 2.1 + time * 1.3) + 0.6 * sin(p.y * 3.3 - time * 1.7) + 0.3 * sin((p.x + p.y) * 5.0 + time * 2.3));
+}
+
+void main() {
+    vec4 world_position = model * vec4(position, 1.0);
+    // only the top face of the tile moves, the bottom stays attached to the sea floor
+    if (normal.y > 0.5 || position.y > 0.05) {
+        world_position.y += wave_height(world_position.xz);
+    }
+
+    mat4 modelview = view * model;
+    v_normal = transpose(inverse(mat3(modelview))) * normal;
+    v_local_position = position;
+    v_world_position = world_position.xyz;
+    v_position = (view * world_position).xyz;
+    gl_Position = perspective * view * world_position;
+}